@@ -0,0 +1,40 @@
+#!/bin/sh
+# Compares the benchmarks of the working tree with the ones of an older commit, tag or branch.
+# Usage: benches/compare_baseline.sh <commit>, e.g. benches/compare_baseline.sh "$(git merge-base HEAD origin/main)"
+# The old commit is checked out in a temporary worktree, the benchmarks are copied into it and its results are
+# saved as a criterion baseline, then the working tree is benchmarked against that baseline.
+# Extra criterion arguments can be passed with BENCH_ARGS, e.g. BENCH_ARGS=--quick
+set -e
+
+if [ -z "$1" ]; then
+    echo "Usage: $0 <commit>" >&2
+    exit 1
+fi
+slotmap_dir=$(cd "$(dirname "$0")/.." && pwd)
+commit=$(git -C "$slotmap_dir" rev-parse --verify --short "$1^{commit}")
+worktree=$(mktemp -d)
+export CARGO_TARGET_DIR="$slotmap_dir/target"
+
+cleanup() {
+    git -C "$slotmap_dir" worktree remove --force "$worktree"
+}
+trap cleanup EXIT
+
+git -C "$slotmap_dir" worktree add --detach "$worktree" "$commit"
+mkdir -p "$worktree/slotmap/benches"
+cp "$slotmap_dir/benches/push_remove.rs" "$worktree/slotmap/benches/"
+if ! grep -q "criterion" "$worktree/slotmap/Cargo.toml"; then
+    cat >> "$worktree/slotmap/Cargo.toml" <<'TOML'
+
+[dev-dependencies]
+rand = "0.8.0"
+criterion = "0.5"
+
+[[bench]]
+name = "push_remove"
+harness = false
+TOML
+fi
+
+(cd "$worktree/slotmap" && cargo bench --bench push_remove -- --save-baseline "$commit" $BENCH_ARGS)
+(cd "$slotmap_dir" && cargo bench --bench push_remove -- --baseline "$commit" $BENCH_ARGS)
@@ -0,0 +1,92 @@
+//! Push and remove throughput of the slotmap, run with `cargo bench`.\
+//! `benches/compare_baseline.sh <commit>` runs the same benchmarks on an older commit and compares them with criterion.
+//!
+//! Taking and freeing slots never allocates, but only `push` measures the O(1) case. A removal that creates or merges
+//! buckets shifts part of the sorted free list, so `remove_random` grows with the amount of free buckets, and
+//! `remove_fragmented` is the worst case: every other slot is free and each removal merges two buckets in the middle
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+use slotmap::Slotmap;
+
+const SIZES: [usize; 3] = [100, 1_000, 10_000];
+
+fn push(c: &mut Criterion) {
+    let mut group = c.benchmark_group("push");
+    for size in SIZES {
+        group.bench_with_input(BenchmarkId::from_parameter(size), &size, |b, &size| {
+            b.iter_batched(
+                || Slotmap::<u32>::with_capacity(size),
+                |mut slotmap| {
+                    for i in 0..size {
+                        black_box(slotmap.push(i as u32));
+                    }
+                    slotmap
+                },
+                BatchSize::SmallInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+fn remove_random(c: &mut Criterion) {
+    let mut group = c.benchmark_group("remove_random");
+    for size in SIZES {
+        group.bench_with_input(BenchmarkId::from_parameter(size), &size, |b, &size| {
+            b.iter_batched(
+                || {
+                    let mut slotmap = Slotmap::<u32>::with_capacity(size);
+                    let mut keys: Vec<_> = (0..size)
+                        .map(|i| slotmap.push(i as u32).unwrap())
+                        .collect();
+                    keys.shuffle(&mut StdRng::seed_from_u64(size as u64));
+                    (slotmap, keys)
+                },
+                |(mut slotmap, keys)| {
+                    for key in keys {
+                        black_box(slotmap.remove(key));
+                    }
+                    slotmap
+                },
+                BatchSize::SmallInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+fn remove_fragmented(c: &mut Criterion) {
+    let mut group = c.benchmark_group("remove_fragmented");
+    for size in SIZES {
+        group.bench_with_input(BenchmarkId::from_parameter(size), &size, |b, &size| {
+            b.iter_batched(
+                || {
+                    let mut slotmap = Slotmap::<u32>::with_capacity(size);
+                    let keys: Vec<_> = (0..size)
+                        .map(|i| slotmap.push(i as u32).unwrap())
+                        .collect();
+                    for key in keys.iter().step_by(2) {
+                        slotmap.remove(*key);
+                    }
+                    //The slots in the middle are removed first, where shifting the buckets costs the most
+                    let mut odd_keys: Vec<_> = keys.into_iter().skip(1).step_by(2).enumerate().collect();
+                    let middle = odd_keys.len() / 2;
+                    odd_keys.sort_by_key(|(position, _)| position.abs_diff(middle));
+                    let odd_keys: Vec<_> = odd_keys.into_iter().map(|(_, key)| key).collect();
+                    (slotmap, odd_keys)
+                },
+                |(mut slotmap, keys)| {
+                    for key in keys {
+                        black_box(slotmap.remove(key));
+                    }
+                    slotmap
+                },
+                BatchSize::SmallInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, push, remove_random, remove_fragmented);
+criterion_main!(benches);
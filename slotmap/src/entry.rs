@@ -0,0 +1,33 @@
+use super::{Key, SlotIndex, SlotKey, Slotmap};
+
+/// A free slot that was taken out of the free list so its key can be known before the value exists.\
+/// If the entry is dropped without inserting a value the slot is returned and its key becomes invalid
+pub struct VacantEntry<'a, V, K: Key = SlotKey> {
+    pub(super) slotmap: &'a mut Slotmap<V, K>,
+    pub(super) slot_index: SlotIndex,
+}
+
+impl<V, K: Key> VacantEntry<'_, V, K> {
+    /// The key the value will have once it is inserted
+    pub fn key(&self) -> K {
+        K::from_slot_key(SlotKey {
+            index: self.slot_index,
+            generation: self.slotmap.slots[self.slot_index].generation,
+        })
+    }
+
+    pub fn insert(self, value: V) -> K {
+        self.slotmap.fill_slot(self.slot_index, value)
+    }
+}
+
+impl<V, K: Key> Drop for VacantEntry<'_, V, K> {
+    fn drop(&mut self) {
+        if !self.slotmap.slots[self.slot_index].taken {
+            //The key could have been handed out, so the generation has to change before reusing the slot
+            if self.slotmap.return_slot(self.slot_index) {
+                self.slotmap.free_list.add_free_slot(self.slot_index);
+            }
+        }
+    }
+}
@@ -1,11 +1,12 @@
-use std::{cell::RefCell, rc::Rc};
+use std::collections::VecDeque;
 
-use super::SlotIndex;
-///A ***Bucket*** is a collection of free slots
+use super::{AllocationPolicy, SlotIndex};
+///A ***Bucket*** is a collection of consecutive free slots
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FreeBucket {
     start_index: usize,
     end_index: usize,
-    pub next_bucket: Option<Rc<RefCell<FreeBucket>>>,
 }
 
 impl FreeBucket {
@@ -13,14 +14,12 @@ impl FreeBucket {
         Self {
             start_index: slot_index,
             end_index: slot_index + 1,
-            next_bucket: None,
         }
     }
     fn new_multiple_slot(slot_index: SlotIndex, capacity: usize) -> Self {
         Self {
             start_index: slot_index,
             end_index: slot_index + capacity,
-            next_bucket: None,
         }
     }
     fn size(&self) -> usize {
@@ -31,283 +30,229 @@ impl FreeBucket {
         self.start_index += 1;
         slot
     }
-    fn get_next_bucket(&self) -> Option<Rc<RefCell<FreeBucket>>> {
-        match &self.next_bucket {
-            Some(next_node) => Some(Rc::clone(next_node)),
-            None => None,
-        }
-    }
 }
 
-/*impl Drop for FreeBucket {
-    fn drop(&mut self) {
-        println!("{} - {} Space dropped", self.start_index, self.end_index);
-    }
-}*/
-
+/// The buckets are stored in a ring buffer sorted by their start index, and never touch each other.\
+/// The `Lifo` and `Fifo` allocation policies also keep the free slots in the order they were freed, the order is not serialized
+///
+/// # Complexity
+/// With `b` buckets:
+/// - Taking the lowest free slot and adding the slots of a bigger capacity are O(1).
+/// - Freeing a slot is a binary search, O(log b), when it grows or shrinks a bucket. When it creates a new bucket or
+///   merges two of them the buckets after it are shifted, O(min(i, b - i)) copies of 16 bytes for the bucket `i`.
+/// - `Lifo` and `Fifo` take their slot from any bucket, so they pay the same search and shift when a bucket is split.
+///
+/// The room for the most buckets the slot table can have is reserved when the capacity changes, so taking and
+/// freeing slots never allocates. `benches/push_remove.rs` measures the worst case with `remove_fragmented`
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
 pub struct FreeList {
-    head: Option<Rc<RefCell<FreeBucket>>>,
+    buckets: VecDeque<FreeBucket>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    policy: AllocationPolicy,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    order: VecDeque<SlotIndex>,
+    /// Number of slots the memory of the free list was reserved for
+    #[cfg_attr(feature = "serde", serde(skip))]
+    slot_capacity: usize,
+}
+
+/// The buckets never touch each other, so there is at most one bucket for every two slots
+fn max_buckets(slot_capacity: usize) -> usize {
+    slot_capacity.div_ceil(2)
 }
 
 impl FreeList {
     pub fn new(capacity: usize) -> Self {
-        let free_space = FreeBucket::new_multiple_slot(0, capacity);
+        let mut buckets = VecDeque::new();
+        if capacity > 0 {
+            buckets.push_back(FreeBucket::new_multiple_slot(0, capacity));
+        }
+        let mut free_list = FreeList {
+            buckets,
+            policy: AllocationPolicy::LowestIndexFirst,
+            order: VecDeque::new(),
+            slot_capacity: 0,
+        };
+        free_list.reserve(capacity);
+        free_list
+    }
 
-        FreeList {
-            head: Some(Rc::new(RefCell::new(free_space))),
+    /// Reserves the memory needed by a slot table with `slot_capacity` slots, so taking and freeing slots never allocates
+    pub fn reserve(&mut self, slot_capacity: usize) {
+        self.slot_capacity = self.slot_capacity.max(slot_capacity);
+        let max_buckets = max_buckets(self.slot_capacity);
+        self.buckets.reserve(max_buckets.saturating_sub(self.buckets.len()));
+        if self.policy.keeps_order() {
+            self.order.reserve(self.slot_capacity.saturating_sub(self.order.len()));
         }
     }
 
-    pub fn get_free_slot(&mut self) -> Option<SlotIndex> {
-        match &self.head {
-            Some(head) => {
-                let size = RefCell::borrow(head).size();
+    pub fn policy(&self) -> AllocationPolicy {
+        self.policy
+    }
 
-                if size > 1 {
-                    //The current head can be shrunk to acomodate the new element
-                    Some(head.borrow_mut().take_single_slot())
-                } else if size == 1 {
-                    //The current head cannot be shrunk
-                    let slot_index = RefCell::borrow(head).start_index;
-                    let next_node = RefCell::borrow(head).get_next_bucket();
-                    //The current head is going to be replaced with the next node
-                    if let Some(next_node) = next_node {
-                        head.borrow_mut().next_bucket = None;
-                        self.head.replace(next_node);
-                    } else {
-                        self.head = None;
-                    }
-                    Some(slot_index)
-                } else {
-                    None
-                }
+    /// Changing the policy resets the order in which slots were freed, the free slots are ordered by index
+    pub fn set_policy(&mut self, policy: AllocationPolicy) {
+        self.policy = policy;
+        self.order.clear();
+        if self.policy.keeps_order() {
+            self.reserve(self.slot_capacity);
+            for bucket in self.buckets.iter() {
+                self.order.extend(bucket.start_index..bucket.end_index);
             }
-            //There are no free slots
-            None => None,
         }
     }
 
-    pub fn merge_buckets(
-        growing_bucket: Rc<RefCell<FreeBucket>>,
-        disapearing_bucket: Rc<RefCell<FreeBucket>>,
-    ) {
-        let disapearing_bucket_borrow = RefCell::borrow(&disapearing_bucket);
-        let mut growing_bucket_mut = RefCell::borrow_mut(&growing_bucket);
-        growing_bucket_mut.end_index = disapearing_bucket_borrow.end_index;
-        let next_bucket = disapearing_bucket_borrow.get_next_bucket();
-        match next_bucket {
-            Some(free_bucket) => {
-                growing_bucket_mut.next_bucket.replace(free_bucket);
-            }
-            None => {
-                growing_bucket_mut.next_bucket = None;
-            }
-        };
+    /// Returns the free slots in the order they were freed, if the policy keeps track of it
+    pub fn order(&self) -> Option<&VecDeque<SlotIndex>> {
+        if self.policy.keeps_order() {
+            Some(&self.order)
+        } else {
+            None
+        }
     }
 
-    pub fn create_list_slice(&self) -> Vec<(usize, usize)> {
-        let mut slice = Vec::<(usize, usize)>::new();
-        if let Some(head) = &self.head {
-            let mut current_bucket = Rc::clone(head);
-            //println!("Create free list?");
-            loop {
-                let (start, end) = (
-                    RefCell::borrow(&current_bucket).start_index,
-                    RefCell::borrow(&current_bucket).end_index,
-                );
-                slice.push((start, end));
-
-                let next_bucket = RefCell::borrow(&current_bucket).get_next_bucket();
-                match next_bucket {
-                    Some(next_bucket) => {
-                        current_bucket = Rc::clone(&next_bucket);
-                    }
-                    None => {
-                        break;
-                    }
-                }
-            }
-        }
-        slice
+    /// Removes every free slot, the policy is not changed
+    pub fn clear(&mut self) {
+        self.buckets.clear();
+        self.order.clear();
     }
 
-    pub fn len(&self) -> usize {
-        let slice = self.create_list_slice();
-        slice.len()
+    pub fn contains(&self, slot_index: SlotIndex) -> bool {
+        let next_index = self
+            .buckets
+            .partition_point(|bucket| bucket.start_index <= slot_index);
+        next_index > 0 && self.buckets[next_index - 1].end_index > slot_index
     }
 
-    pub fn get_tail(&self) -> Option<Rc<RefCell<FreeBucket>>>{
-        match &self.head {
-            Some(head) => {
-                let mut current_tail = Rc::clone(head);
-                //println!("Get Tail?");
-                loop {
-                    let next_bucket = RefCell::borrow(&current_tail).get_next_bucket();
-                    match next_bucket {
-                        Some(next_bucket) => {
-                            current_tail = next_bucket;
-                        },
-                        None => {break;},
-                    }
-                }
+    pub fn get_free_slot(&mut self) -> Option<SlotIndex> {
+        let slot_index = match self.policy {
+            AllocationPolicy::LowestIndexFirst => return self.take_lowest_slot(),
+            AllocationPolicy::Lifo => self.order.pop_back()?,
+            AllocationPolicy::Fifo => self.order.pop_front()?,
+        };
+        self.remove_slot(slot_index);
+        Some(slot_index)
+    }
 
-                Some(Rc::clone(&current_tail))
-            },
-            None => None, // If there is no head, then the  
+    fn take_lowest_slot(&mut self) -> Option<SlotIndex> {
+        let head = self.buckets.front_mut()?;
+        let slot_index = head.take_single_slot();
+        if head.size() == 0 {
+            //The current head is going to be replaced with the next bucket
+            self.buckets.pop_front();
         }
+        Some(slot_index)
     }
 
-    /// Adds a bucket to the tail or grows the current tail if possible 
-    pub fn add_free_bucket_to_tail(&mut self, index: SlotIndex, size: usize){
-        let new_bucket = FreeBucket::new_multiple_slot(index, size);
-        let new_bucket = Rc::new(RefCell::new(new_bucket));
-        match self.get_tail() {
-            Some(tail) => {
-                let tail_end_index = RefCell::borrow(&tail).end_index;
-                if tail_end_index == index{
-                    //Merge new bucket with existing tail, as it would be next to it in memory
-                    FreeList::merge_buckets(tail, new_bucket);
-                } else {
-                    //New bucket is not connected to the existing tail, so it is going to become the new tail
-                    RefCell::borrow_mut(&tail).next_bucket.replace(new_bucket);
-                }
-            },
-            None => {
-                // There are no buckets so this bucket is going to be added as the head
-                self.head.replace(new_bucket);
-            },
-        }
+    pub fn create_list_slice(&self) -> Vec<(usize, usize)> {
+        self.buckets
+            .iter()
+            .map(|bucket| (bucket.start_index, bucket.end_index))
+            .collect()
     }
 
-    pub fn add_free_slot(&mut self, slot_index: SlotIndex) {
-        match &self.head {
-            Some(head) => {
-                let head_start_index = RefCell::borrow(head).start_index;
-                println!(
-                    "Head start index {} || Slot index {}",
-                    head_start_index, slot_index
-                );
+    pub fn len(&self) -> usize {
+        self.buckets.len()
+    }
 
-                if slot_index < head_start_index {
-                    //The current slot is before the head
-                    if head_start_index - 1 == slot_index {
-                        //If the new slot is right before the current head, then expand backwards
-                        RefCell::borrow_mut(head).start_index -= 1;
-                    } else {
-                        //It is farther back than a single slot away from the head
-                        //Create a new bucket and make it the new head
+    /// Removes a free slot from the bucket that contains it, splitting the bucket if the slot is in the middle
+    fn remove_slot(&mut self, slot_index: SlotIndex) {
+        let bucket_index = self
+            .buckets
+            .partition_point(|bucket| bucket.start_index <= slot_index)
+            - 1;
+        let bucket = &mut self.buckets[bucket_index];
+        debug_assert!(bucket.end_index > slot_index, "Slot {} is not free", slot_index);
 
-                        let current_head = Rc::clone(head);
-                        let mut new_bucket = FreeBucket::new_single_slot(slot_index);
-                        new_bucket.next_bucket = Some(current_head);
-                        self.head.replace(Rc::new(RefCell::new(new_bucket)));
+        if bucket.size() == 1 {
+            self.buckets.remove(bucket_index);
+        } else if bucket.start_index == slot_index {
+            bucket.start_index += 1;
+        } else if bucket.end_index == slot_index + 1 {
+            bucket.end_index -= 1;
+        } else {
+            let tail = FreeBucket::new_multiple_slot(slot_index + 1, bucket.end_index - slot_index - 1);
+            bucket.end_index = slot_index;
+            self.buckets.insert(bucket_index + 1, tail);
+        }
+    }
 
-                        #[cfg(test)]
-                        {
-                            println!("Changed head");
-                        }
-                    }
-                } else {
-                    let mut current_bucket = Rc::clone(head);
-                    //println!("Free slot?");
-                    loop {
-                        let range_start = RefCell::borrow(&current_bucket).end_index;
-                        let next_bucket = RefCell::borrow(&current_bucket).get_next_bucket();
-                        match &next_bucket {
-                            Some(next_bucket) => {
-                                //there is a next bucket
-                                let range_end = RefCell::borrow(next_bucket).start_index;
+    /// Removes all the free slots with an index equal or greater than `len`
+    pub fn truncate(&mut self, len: usize) {
+        while let Some(tail) = self.buckets.back_mut() {
+            if tail.start_index >= len {
+                self.buckets.pop_back();
+            } else {
+                tail.end_index = tail.end_index.min(len);
+                break;
+            }
+        }
+        //The memory needed by the remaining slots is kept
+        self.slot_capacity = len;
+        self.buckets.shrink_to(max_buckets(len));
+        self.order.retain(|slot_index| *slot_index < len);
+        self.order.shrink_to(if self.policy.keeps_order() { len } else { 0 });
+    }
 
-                                #[cfg(test)]
-                                {
-                                    println!("Range start {} || end {}", range_start, range_end);
-                                }
+    /// Adds a bucket to the tail or grows the current tail if possible
+    pub fn add_free_bucket_to_tail(&mut self, index: SlotIndex, size: usize) {
+        self.reserve(index + size);
+        if self.policy.keeps_order() {
+            self.order.extend(index..index + size);
+        }
+        match self.buckets.back_mut() {
+            //Merge new bucket with existing tail, as it would be next to it in memory
+            Some(tail) if tail.end_index == index => {
+                tail.end_index += size;
+            }
+            //New bucket is not connected to the existing tail, so it is going to become the new tail
+            _ => {
+                self.buckets
+                    .push_back(FreeBucket::new_multiple_slot(index, size));
+            }
+        }
+    }
 
-                                if slot_index >= range_start && slot_index < range_end {
-                                    //The new free slot is in between the current bucket and the next bucket
+    pub fn add_free_slot(&mut self, slot_index: SlotIndex) {
+        if self.policy.keeps_order() {
+            self.order.push_back(slot_index);
+        }
 
-                                    if (range_end - range_start) == 1 {
-                                        //the free slot would merge the current and next bucket
-                                        FreeList::merge_buckets(
-                                            Rc::clone(&current_bucket),
-                                            Rc::clone(next_bucket),
-                                        );
-                                        #[cfg(test)]
-                                        {
-                                            println!("Merged buckets");
-                                        }
-                                        break;
-                                    } else if range_start == slot_index {
-                                        //the free slot needs to be attached to the current bucket
-                                        RefCell::borrow_mut(&current_bucket).end_index += 1;
-                                        #[cfg(test)]
-                                        {
-                                            println!("Expand current bucket");
-                                        }
-                                    } else if range_end == slot_index + 1 {
-                                        //the free slot grows the next bucket backwards
-                                        RefCell::borrow_mut(&next_bucket).start_index -= 1;
-                                        #[cfg(test)]
-                                        {
-                                            println!("Expand next bucket");
-                                        }
-                                    } else {
-                                        //A new bucket needs to be created because the free slot is in between the current and next buckets without touching either
-                                        //the current bucket is going to point to the new bucket, and the new bucket is going to point to the next bucket
-                                        let mut new_bucket =
-                                            FreeBucket::new_single_slot(slot_index);
-                                        new_bucket.next_bucket = Some(Rc::clone(next_bucket));
-                                        RefCell::borrow_mut(&current_bucket)
-                                            .next_bucket
-                                            .replace(Rc::new(RefCell::new(new_bucket)));
+        //Index of the first bucket that starts after the free slot, the free slot is placed right before it
+        let next_index = self
+            .buckets
+            .partition_point(|bucket| bucket.start_index <= slot_index);
 
-                                        #[cfg(test)]
-                                        {
-                                            println!("Create bucket in between");
-                                        }
-                                    }
-                                    break; //Free slot added into the list
-                                } else {
-                                    //The slot is not between the current and next bucket, continue searching
-                                    current_bucket = Rc::clone(next_bucket);
+        debug_assert!(
+            next_index == 0 || self.buckets[next_index - 1].end_index <= slot_index,
+            "Slot {} is already free",
+            slot_index
+        );
 
-                                    #[cfg(test)]
-                                    {
-                                        println!("Continue -----------------");
-                                    }
-                                    continue; //Continue searching
-                                }
-                            }
-                            None => {
-                                //There is no next bucket
-                                let mut current_bucket = RefCell::borrow_mut(&current_bucket);
-                                if current_bucket.end_index == slot_index {
-                                    //The new free slot is right in the end of the current bucket, so the current bucket just needs to grow
-                                    current_bucket.end_index += 1;
-                                } else {
-                                    //The new free slot is after
-                                    current_bucket.next_bucket = Some(Rc::new(RefCell::new(
-                                        FreeBucket::new_single_slot(slot_index),
-                                    )));
+        let touches_prev = next_index > 0 && self.buckets[next_index - 1].end_index == slot_index;
+        let touches_next =
+            next_index < self.buckets.len() && self.buckets[next_index].start_index == slot_index + 1;
 
-                                    #[cfg(test)]
-                                    {
-                                        println!("Create bucket after?");
-                                    }
-                                }
-                                break; //Free slot added into the list
-                            }
-                        }
-                    }
-                }
+        match (touches_prev, touches_next) {
+            (true, true) => {
+                //the free slot would merge the previous and next bucket
+                self.buckets[next_index - 1].end_index = self.buckets[next_index].end_index;
+                self.buckets.remove(next_index);
+            }
+            (true, false) => {
+                //the free slot needs to be attached to the previous bucket
+                self.buckets[next_index - 1].end_index += 1;
+            }
+            (false, true) => {
+                //the free slot grows the next bucket backwards
+                self.buckets[next_index].start_index -= 1;
             }
-            None => {
-                //There were no free slots before, so this is the new head
-                self.head
-                    .replace(Rc::new(RefCell::new(FreeBucket::new_single_slot(
-                        slot_index,
-                    ))));
-                //Free slot added into the list
+            (false, false) => {
+                //A new bucket needs to be created because the free slot does not touch any bucket
+                self.buckets
+                    .insert(next_index, FreeBucket::new_single_slot(slot_index));
             }
         }
     }
@@ -0,0 +1,104 @@
+use std::{marker::PhantomData, slice, vec};
+
+use super::{Key, Slot, SlotIndex, SlotKey};
+
+#[inline]
+pub(super) fn create_key<K: Key>(slots: &[Slot], slot_index: SlotIndex) -> K {
+    K::from_slot_key(SlotKey {
+        index: slot_index,
+        generation: slots[slot_index].generation,
+    })
+}
+
+/// Iterates over the values in the same order as `Slotmap::get_iter`, together with the key of each value
+pub struct Iter<'a, V, K: Key = SlotKey> {
+    pub(super) values: slice::Iter<'a, V>,
+    pub(super) values_slot: slice::Iter<'a, SlotIndex>,
+    pub(super) slots: &'a [Slot],
+    pub(super) key_type: PhantomData<K>,
+}
+
+impl<'a, V, K: Key> Iterator for Iter<'a, V, K> {
+    type Item = (K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let value = self.values.next()?;
+        let slot_index = self.values_slot.next()?;
+        Some((create_key(self.slots, *slot_index), value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.values.size_hint()
+    }
+}
+
+impl<V, K: Key> ExactSizeIterator for Iter<'_, V, K> {}
+
+/// Iterates over the values in the same order as `Slotmap::get_iter_mut`, together with the key of each value
+pub struct IterMut<'a, V, K: Key = SlotKey> {
+    pub(super) values: slice::IterMut<'a, V>,
+    pub(super) values_slot: slice::Iter<'a, SlotIndex>,
+    pub(super) slots: &'a [Slot],
+    pub(super) key_type: PhantomData<K>,
+}
+
+impl<'a, V, K: Key> Iterator for IterMut<'a, V, K> {
+    type Item = (K, &'a mut V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let value = self.values.next()?;
+        let slot_index = self.values_slot.next()?;
+        Some((create_key(self.slots, *slot_index), value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.values.size_hint()
+    }
+}
+
+impl<V, K: Key> ExactSizeIterator for IterMut<'_, V, K> {}
+
+/// Iterates over the keys of all the values in the slotmap, in the same order as the values
+pub struct Keys<'a, K: Key = SlotKey> {
+    pub(super) values_slot: slice::Iter<'a, SlotIndex>,
+    pub(super) slots: &'a [Slot],
+    pub(super) key_type: PhantomData<K>,
+}
+
+impl<K: Key> Iterator for Keys<'_, K> {
+    type Item = K;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let slot_index = self.values_slot.next()?;
+        Some(create_key(self.slots, *slot_index))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.values_slot.size_hint()
+    }
+}
+
+impl<K: Key> ExactSizeIterator for Keys<'_, K> {}
+
+/// Moves all the values out of the slotmap together with the key they had.\
+/// The keys are already invalid when the iterator is created, and the values that are not consumed are dropped
+pub struct Drain<'a, V, K: Key = SlotKey> {
+    pub(super) values: vec::Drain<'a, V>,
+    pub(super) keys: vec::IntoIter<K>,
+}
+
+impl<V, K: Key> Iterator for Drain<'_, V, K> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let value = self.values.next()?;
+        let key = self.keys.next()?;
+        Some((key, value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.values.size_hint()
+    }
+}
+
+impl<V, K: Key> ExactSizeIterator for Drain<'_, V, K> {}
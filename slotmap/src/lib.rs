@@ -1,15 +1,31 @@
+/// Creates a key type that can only be used with the slotmaps created for it, e.g. `Slotmap<RenderTexture, TextureKey>`.\
+/// Attributes written before the name are added to the key struct
+/// ```compile_fail
+/// use slotmap::prelude::*;
+/// create_custom_key!(TextureKey;);
+/// create_custom_key!(EntityKey;);
+///
+/// let mut textures = Slotmap::<u32, TextureKey>::with_capacity(4);
+/// let mut entities = Slotmap::<u32, EntityKey>::with_capacity(4);
+/// let entity_key = entities.push(0).unwrap();
+/// textures.get_value(&entity_key);
+/// ```
 #[macro_export]
 macro_rules! create_custom_key {
     (
+        $(#[$attribute:meta])*
         $struct_name:ident;
     ) => {
-        #[derive(Clone, Copy)]
-        pub struct $struct_name (pub SlotKey);
-        impl Deref for $struct_name{
-            type Target = SlotKey;
-        
-            fn deref(&self) -> &Self::Target {
-                &self.0
+        $(#[$attribute])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub struct $struct_name($crate::SlotKey);
+        impl $crate::Key for $struct_name {
+            fn from_slot_key(slot_key: $crate::SlotKey) -> Self {
+                Self(slot_key)
+            }
+
+            fn slot_key(&self) -> $crate::SlotKey {
+                self.0
             }
         }
     };
@@ -17,7 +33,7 @@ macro_rules! create_custom_key {
 
 pub mod prelude;
 
-use std::{slice::{Iter, IterMut}};
+use std::{marker::PhantomData, slice};
 
 pub type ValueIndex = usize;
 pub type SlotIndex = usize;
@@ -26,29 +42,127 @@ pub type Generation = u32;
 mod free_list;
 use free_list::*;
 
+mod secondary;
+pub use secondary::SecondarySlotmap;
+
+pub mod iter;
+use iter::{Drain, Iter, IterMut, Keys};
+
+mod entry;
+pub use entry::VacantEntry;
+
+mod validation;
+pub use validation::ValidationError;
+
+#[cfg(feature = "serde")]
+mod serialization;
+
+#[cfg(feature = "rayon")]
+mod parallel;
+
+#[cfg(test)]
+mod test;
+
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Slot {
     index: ValueIndex,
     generation: Generation,
     taken: bool,
 }
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SlotKey {
     index: SlotIndex,
     generation: Generation,
 }
 
-/// This structure is optimized in the following order iteration > random acess > pushing objects > removing objects
-pub struct Slotmap<V> {
+/// A key that points to a value in a slotmap, the slotmap only accepts the key type it was created with
+pub trait Key: Copy {
+    fn from_slot_key(slot_key: SlotKey) -> Self;
+    fn slot_key(&self) -> SlotKey;
+}
+
+impl Key for SlotKey {
+    fn from_slot_key(slot_key: SlotKey) -> Self {
+        slot_key
+    }
+
+    fn slot_key(&self) -> SlotKey {
+        *self
+    }
+}
+
+/// Defines how the slotmap capacity changes when `push` is called on a full slotmap
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GrowthPolicy {
+    /// The capacity is only increased manually with `reserve_exact`, `push` returns `None` on a full slotmap
+    #[default]
+    Fixed,
+    /// The capacity is doubled (an empty slotmap grows to a single slot)
+    Double,
+    /// The capacity is increased by the specified amount of slots
+    Increment(usize),
+    /// The capacity is doubled until it reaches the specified maximum capacity, `push` returns `None` after that
+    Capped(usize),
+}
+
+impl GrowthPolicy {
+    /// Returns the amount of slots that need to be added to a slotmap with the given capacity
+    fn get_extra_capacity(&self, capacity: usize) -> usize {
+        match *self {
+            GrowthPolicy::Fixed => 0,
+            GrowthPolicy::Double => capacity.max(1),
+            GrowthPolicy::Increment(increment) => increment,
+            GrowthPolicy::Capped(max_capacity) => {
+                capacity.max(1).min(max_capacity.saturating_sub(capacity))
+            }
+        }
+    }
+}
+
+/// Defines which free slot is used when a value is pushed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AllocationPolicy {
+    /// The free slot with the lowest index is used, which keeps the used slots packed at the start
+    #[default]
+    LowestIndexFirst,
+    /// The most recently freed slot is used first, slots added when growing count as freed at that moment
+    Lifo,
+    /// The slot that has been free for the longest time is used first, which delays reusing the generations of a slot
+    Fifo,
+}
+
+impl AllocationPolicy {
+    fn keeps_order(&self) -> bool {
+        !matches!(self, AllocationPolicy::LowestIndexFirst)
+    }
+}
+
+/// This structure is optimized in the following order iteration > random acess > pushing objects > removing objects\
+/// The key type defaults to `SlotKey`, custom key types can be created with `create_custom_key!`.\
+/// The slotmap is `Send` and `Sync` when the values are, so it can be shared with other threads
+pub struct Slotmap<V, K: Key = SlotKey> {
     values: Vec<V>,
     ///Array with the slot indexes for the slots that are pointing to a value, this array has the same order as the Values array
     values_slot: Vec<SlotIndex>,
     slots: Vec<Slot>,
     free_list: FreeList,
+    growth_policy: GrowthPolicy,
+    /// Generation of the slots created when growing, it is increased when `shrink_to_fit` removes slots
+    /// so the keys that pointed to the removed slots do not become valid again
+    generation_floor: Generation,
+    key_type: PhantomData<K>,
 }
 
-impl<V> Slotmap<V> {
+impl<V, K: Key> Slotmap<V, K> {
     pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_growth_policy(capacity, GrowthPolicy::Fixed)
+    }
+
+    pub fn with_growth_policy(capacity: usize, growth_policy: GrowthPolicy) -> Self {
         let values = Vec::<V>::with_capacity(capacity);
         let values_slot = Vec::<SlotIndex>::with_capacity(capacity);
         let slots = vec![
@@ -67,11 +181,33 @@ impl<V> Slotmap<V> {
             values_slot,
             slots,
             free_list,
+            growth_policy,
+            generation_floor: 0,
+            key_type: PhantomData,
         }
     }
 
+    pub fn growth_policy(&self) -> GrowthPolicy {
+        self.growth_policy
+    }
+
+    pub fn set_growth_policy(&mut self, growth_policy: GrowthPolicy) {
+        self.growth_policy = growth_policy;
+    }
+
+    pub fn allocation_policy(&self) -> AllocationPolicy {
+        self.free_list.policy()
+    }
+
+    /// The order in which slots were freed is only tracked by the `Lifo` and `Fifo` policies,
+    /// so after changing the policy the current free slots are used in index order
+    pub fn set_allocation_policy(&mut self, allocation_policy: AllocationPolicy) {
+        self.free_list.set_policy(allocation_policy);
+    }
+
+    /// The capacity is the amount of slots, which is the maximum amount of values that can be stored without growing
     pub fn capacity(&self) -> usize {
-        self.values.capacity()
+        self.slots.len()
     }
 
     pub fn len(&self) -> usize {
@@ -86,53 +222,130 @@ impl<V> Slotmap<V> {
 		self.free_list.create_list_slice()
 	}
 
-    pub fn get_iter(&self) -> Iter<'_, V> {
+    pub fn get_iter(&self) -> slice::Iter<'_, V> {
         self.values.iter()
     }
 
-    pub fn get_iter_mut(&mut self) -> IterMut<'_, V> {
+    pub fn get_iter_mut(&mut self) -> slice::IterMut<'_, V> {
         self.values.iter_mut()
     }
 
-    pub fn get_value(&self, key: &SlotKey) -> Option<&V> {
-        if self.is_valid(&key) {
-            Some(&self.values[self.slots[key.index].index])
+    /// Same as `get_iter`, but each value is paired with its key
+    pub fn iter(&self) -> Iter<'_, V, K> {
+        Iter {
+            values: self.values.iter(),
+            values_slot: self.values_slot.iter(),
+            slots: &self.slots,
+            key_type: PhantomData,
+        }
+    }
+
+    /// Same as `get_iter_mut`, but each value is paired with its key
+    pub fn iter_mut(&mut self) -> IterMut<'_, V, K> {
+        IterMut {
+            values: self.values.iter_mut(),
+            values_slot: self.values_slot.iter(),
+            slots: &self.slots,
+            key_type: PhantomData,
+        }
+    }
+
+    pub fn keys(&self) -> Keys<'_, K> {
+        Keys {
+            values_slot: self.values_slot.iter(),
+            slots: &self.slots,
+            key_type: PhantomData,
+        }
+    }
+
+    pub fn get_value(&self, key: &K) -> Option<&V> {
+        if self.is_valid(key) {
+            Some(&self.values[self.slots[key.slot_key().index].index])
         } else {
             None
         }
     }
 
-    pub fn get_value_mut(&mut self, key: &SlotKey) -> Option<&mut V> {
-        if self.is_valid(&key) {
-            Some(&mut self.values[self.slots[key.index].index])
+    pub fn get_value_mut(&mut self, key: &K) -> Option<&mut V> {
+        if self.is_valid(key) {
+            Some(&mut self.values[self.slots[key.slot_key().index].index])
         } else {
             None
         }
     }
 
+    /// Returns mutable references to the values of all the keys at the same time.\
+    /// Returns `None` if any key is not valid or if two keys point to the same value
+    pub fn get_disjoint_mut<const N: usize>(&mut self, keys: [K; N]) -> Option<[&mut V; N]> {
+        let mut value_indices = [0; N];
+        for (i, key) in keys.iter().enumerate() {
+            if !self.is_valid(key) {
+                return None;
+            }
+            value_indices[i] = self.slots[key.slot_key().index].index;
+        }
+
+        //The values are split off in the order of their indices, a repeated index cannot be split off twice
+        let mut order: [usize; N] = std::array::from_fn(|i| i);
+        order.sort_unstable_by_key(|i| value_indices[*i]);
+        let mut values: [Option<&mut V>; N] = [(); N].map(|_| None);
+        let mut rest = self.values.as_mut_slice();
+        let mut rest_start = 0;
+        for i in order {
+            let value_index = value_indices[i];
+            if value_index < rest_start {
+                return None;
+            }
+            let (value, tail) = std::mem::take(&mut rest)[value_index - rest_start..].split_first_mut()?;
+            values[i] = Some(value);
+            rest = tail;
+            rest_start = value_index + 1;
+        }
+        Some(values.map(|value| value.expect("Every value was split off")))
+    }
+
+    /// Returns mutable references to the values of all the keys without checking them
+    ///
+    /// # Safety
+    /// All the keys have to be valid and no two keys can point to the same value
+    pub unsafe fn get_disjoint_unchecked_mut<const N: usize>(&mut self, keys: [K; N]) -> [&mut V; N] {
+        let values = self.values.as_mut_ptr();
+        keys.map(|key| &mut *values.add(self.slots.get_unchecked(key.slot_key().index).index))
+    }
+
     pub fn is_empty(&self) -> bool {
         self.values.is_empty()
     }
 
+    /// Keys are only valid if they point to a taken slot with the same generation.\
+    /// Keys from other slotmaps with indexes out of range are not valid
     #[inline]
-    pub fn is_valid(&self, key: &SlotKey) -> bool {
-        self.slots[key.index].generation == key.generation
+    pub fn is_valid(&self, key: &K) -> bool {
+        let key = key.slot_key();
+        match self.slots.get(key.index) {
+            Some(slot) => slot.taken && slot.generation == key.generation,
+            None => false,
+        }
     }
 
-    fn return_slot(&mut self, index: SlotIndex) {
+    /// Makes the keys that point to the slot invalid, returns `false` if the slot was retired.\
+    /// A slot is retired when its generation reaches `Generation::MAX`, it is never used again
+    /// because increasing its generation would make old keys valid again
+    fn return_slot(&mut self, index: SlotIndex) -> bool {
         let slot = &mut self.slots[index];
         slot.generation += 1;
         slot.taken = false;
+        slot.generation != Generation::MAX
     }
 
-    fn take_slot(&mut self, index: SlotIndex, v_index: ValueIndex) -> SlotKey {
+    fn take_slot(&mut self, index: SlotIndex, v_index: ValueIndex) -> K {
         let slot = &mut self.slots[index];
         slot.taken = true;
         slot.index = v_index;
-        SlotKey {
+        K::from_slot_key(SlotKey {
             index,
             generation: slot.generation,
-        }
+        })
     }
 
     /// This function uses the `Vec::reserve_exact` internally to increase the available space
@@ -140,22 +353,63 @@ impl<V> Slotmap<V> {
     /// Increased capacity is equatl to `max( 0, self.len() + aditional - self.capacity() )`
     pub fn reserve_exact(&mut self, aditional: usize) -> Option<usize> {
         let current_capacity = self.capacity();
-        
+        let new_capacity = self.len() + aditional;
+
+        if new_capacity <= current_capacity {
+            return None;
+        }
+
+        let extra_capacity = new_capacity - current_capacity;
         self.values.reserve_exact(aditional);
         self.values_slot.reserve_exact(aditional);
+        self.slots.resize(
+            new_capacity,
+            Slot {
+                index: 0,
+                generation: self.generation_floor,
+                taken: false,
+            },
+        );
 
-        let new_capacity = self.capacity();
+        self.free_list.add_free_bucket_to_tail(current_capacity, extra_capacity);
+        Some(extra_capacity)
+    }
 
-        let extra_capacity = new_capacity - current_capacity;
-        if extra_capacity > 0 {
-            self.free_list.add_free_bucket_to_tail(current_capacity, extra_capacity);
-            return Some(extra_capacity)
+    /// Removes the free slots at the end of the slot table and releases the unused memory.\
+    /// Retired slots and slots before the last taken slot are kept, so the removed amount depends on the allocation policy.
+    /// Returns the amount of slots that were removed
+    pub fn shrink_to_fit(&mut self) -> usize {
+        let mut new_capacity = self.slots.len();
+        while new_capacity > 0 && self.free_list.contains(new_capacity - 1) {
+            new_capacity -= 1;
         }
-        None
+
+        let removed_slots = self.slots.len() - new_capacity;
+        for slot in self.slots[new_capacity..].iter() {
+            self.generation_floor = self.generation_floor.max(slot.generation);
+        }
+        self.slots.truncate(new_capacity);
+        self.free_list.truncate(new_capacity);
+
+        self.values.shrink_to_fit();
+        self.values_slot.shrink_to_fit();
+        self.slots.shrink_to_fit();
+        removed_slots
     }
 
-    pub fn remove(&mut self, key: SlotKey) -> Option<V> {
+    /// Increases the capacity following the growth policy, returns `false` if the slotmap could not grow
+    fn grow(&mut self) -> bool {
+        let extra_capacity = self.growth_policy.get_extra_capacity(self.capacity());
+        if extra_capacity == 0 {
+            return false;
+        }
+        let aditional = self.capacity() - self.len() + extra_capacity;
+        self.reserve_exact(aditional).is_some()
+    }
+
+    pub fn remove(&mut self, key: K) -> Option<V> {
         if self.is_valid(&key) {
+            let key = key.slot_key();
 			//todo!("If the value that is going to be swaped is the last array element, then the swap is not perfromed and the element is just removed, the current code does not reflect that and panics in that case");
             let val_index = self.slots[key.index].index;
             
@@ -163,7 +417,7 @@ impl<V> Slotmap<V> {
             self.values_slot.swap_remove(val_index);
 
             //Make all the keys that pointed to that value invalid
-            self.return_slot(key.index);
+            let reusable_slot = self.return_slot(key.index);
 
             //If slotmap is not empty, update the affected slot to make sure it points to the right index,
             //becuase its value was swaped, and now it is pointing to an invalid space in the vector
@@ -176,7 +430,9 @@ impl<V> Slotmap<V> {
             }
 
             //the slot on index {key.index} is free now
-            self.free_list.add_free_slot(key.index);
+            if reusable_slot {
+                self.free_list.add_free_slot(key.index);
+            }
 
             Some(value)
         } else {
@@ -184,21 +440,170 @@ impl<V> Slotmap<V> {
         }
     }
 
-    pub fn push(&mut self, value: V) -> Option<SlotKey> {
-        if self.values.capacity() == self.values.len() {
-            None
-        } else {
-            self.values.push(value);
-            //Get an available lot to put as a stable renference to the added element
-            match self.free_list.get_free_slot() {
-                Some(free_slot) => {
-                    self.values_slot.push(free_slot);
-                    let slot_key = self.take_slot(free_slot, self.values.len() - 1);
-
-                    Some(slot_key)
+    /// Same as `remove`, but the values after the removed value are shifted instead of swapping the last value into its place,
+    /// so the order of the remaining values does not change.\
+    /// This is `O(n)` because the slots of all the shifted values have to be updated
+    pub fn remove_ordered(&mut self, key: K) -> Option<V> {
+        if !self.is_valid(&key) {
+            return None;
+        }
+        let key = key.slot_key();
+        let val_index = self.slots[key.index].index;
+
+        let value = self.values.remove(val_index);
+        self.values_slot.remove(val_index);
+
+        let reusable_slot = self.return_slot(key.index);
+        self.update_slot_indices(val_index);
+
+        if reusable_slot {
+            self.free_list.add_free_slot(key.index);
+        }
+
+        Some(value)
+    }
+
+    /// Sorts the values with the comparator function, the keys stay valid and the iteration order follows the sort.\
+    /// The sort is stable, so equal values keep their order
+    pub fn sort_by<F>(&mut self, mut compare: F)
+    where
+        F: FnMut(&V, &V) -> std::cmp::Ordering,
+    {
+        //Only the permutation is sorted, so a panicking comparator leaves the values untouched
+        let mut order: Vec<ValueIndex> = (0..self.values.len()).collect();
+        order.sort_by(|a, b| compare(&self.values[*a], &self.values[*b]));
+
+        //Each cycle of the permutation is followed with swaps, 'order[i] == i' marks the positions already in place
+        for start in 0..order.len() {
+            let mut current = start;
+            while order[current] != start {
+                let next = order[current];
+                self.values.swap(current, next);
+                self.values_slot.swap(current, next);
+                order[current] = current;
+                current = next;
+            }
+            order[current] = current;
+        }
+        self.update_slot_indices(0);
+    }
+
+    /// Same as `sort_by`, but the values are compared with the key returned by the function
+    pub fn sort_by_key<T, F>(&mut self, mut f: F)
+    where
+        T: Ord,
+        F: FnMut(&V) -> T,
+    {
+        self.sort_by(|a, b| f(a).cmp(&f(b)));
+    }
+
+    /// Makes the slots of the values starting at `first_value` point to the current position of their value
+    fn update_slot_indices(&mut self, first_value: ValueIndex) {
+        for val_index in first_value..self.values_slot.len() {
+            let slot_index = self.values_slot[val_index];
+            self.slots[slot_index].index = val_index;
+        }
+    }
+
+    /// Removes all the values for which the function returns `false`.\
+    /// Removed values are swapped with the last value, so the order of the remaining values can change
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(K, &mut V) -> bool,
+    {
+        let mut val_index = 0;
+        while val_index < self.values.len() {
+            let slot_index = self.values_slot[val_index];
+            let key = K::from_slot_key(SlotKey {
+                index: slot_index,
+                generation: self.slots[slot_index].generation,
+            });
+            if f(key, &mut self.values[val_index]) {
+                val_index += 1;
+            } else {
+                //The last value is swapped into 'val_index', so it is checked on the next iteration
+                self.remove(key);
+            }
+        }
+    }
+
+    /// Removes all the values, the capacity is not changed and all the keys become invalid
+    pub fn clear(&mut self) {
+        self.return_all_slots();
+        self.values.clear();
+    }
+
+    /// Removes all the values and returns them with the keys they had, the keys are invalid once this function returns
+    pub fn drain(&mut self) -> Drain<'_, V, K> {
+        let keys: Vec<K> = self.keys().collect();
+        self.return_all_slots();
+        Drain {
+            values: self.values.drain(..),
+            keys: keys.into_iter(),
+        }
+    }
+
+    /// Makes every key invalid and frees all the slots, the values have to be removed by the caller
+    fn return_all_slots(&mut self) {
+        for val_index in 0..self.values_slot.len() {
+            let slot_index = self.values_slot[val_index];
+            self.return_slot(slot_index);
+        }
+        self.values_slot.clear();
+
+        self.free_list.clear();
+        for (index, slot) in self.slots.iter().enumerate() {
+            if slot.generation != Generation::MAX {
+                self.free_list.add_free_bucket_to_tail(index, 1);
+            }
+        }
+    }
+
+    /// Takes a slot out of the free list, if there are no free slots the capacity is increased following the growth policy
+    fn next_free_slot(&mut self) -> Option<SlotIndex> {
+        match self.free_list.get_free_slot() {
+            Some(free_slot) => Some(free_slot),
+            None => {
+                if !self.grow() {
+                    return None;
                 }
-                None => None,
+                self.free_list.get_free_slot()
             }
         }
     }
+
+    /// Puts the value at the end of the values and makes the slot point to it
+    fn fill_slot(&mut self, slot_index: SlotIndex, value: V) -> K {
+        self.values.push(value);
+        self.values_slot.push(slot_index);
+        self.take_slot(slot_index, self.values.len() - 1)
+    }
+
+    /// Pushes a value into the slotmap, if the slotmap is full its capacity is increased following the growth policy.\
+    /// Returns `None` if the slotmap is full and cannot grow
+    pub fn push(&mut self, value: V) -> Option<K> {
+        //Get an available slot to put as a stable renference to the added element
+        let free_slot = self.next_free_slot()?;
+        Some(self.fill_slot(free_slot, value))
+    }
+
+    /// Same as `push`, but the value is created with the key it is going to have
+    pub fn insert_with_key<F>(&mut self, f: F) -> Option<K>
+    where
+        F: FnOnce(K) -> V,
+    {
+        let entry = self.vacant_entry()?;
+        let value = f(entry.key());
+        Some(entry.insert(value))
+    }
+
+    /// Reserves a slot so its key can be used before the value is inserted.\
+    /// Returns `None` if the slotmap is full and cannot grow
+    pub fn vacant_entry(&mut self) -> Option<VacantEntry<'_, V, K>> {
+        let slot_index = self.next_free_slot()?;
+        Some(VacantEntry {
+            slotmap: self,
+            slot_index,
+        })
+    }
 }
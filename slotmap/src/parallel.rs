@@ -0,0 +1,23 @@
+use rayon::prelude::*;
+
+use super::{iter::create_key, Key, Slotmap};
+
+impl<V: Send + Sync, K: Key + Send + Sync> Slotmap<V, K> {
+    /// Same as `iter`, but the values are visited in parallel by the rayon thread pool
+    pub fn par_iter(&self) -> impl IndexedParallelIterator<Item = (K, &V)> + '_ {
+        let slots = &self.slots;
+        self.values_slot
+            .par_iter()
+            .zip(self.values.par_iter())
+            .map(move |(slot_index, value)| (create_key(slots, *slot_index), value))
+    }
+
+    /// Same as `iter_mut`, but the values are visited in parallel by the rayon thread pool
+    pub fn par_iter_mut(&mut self) -> impl IndexedParallelIterator<Item = (K, &mut V)> + '_ {
+        let slots = &self.slots;
+        self.values_slot
+            .par_iter()
+            .zip(self.values.par_iter_mut())
+            .map(move |(slot_index, value)| (create_key(slots, *slot_index), value))
+    }
+}
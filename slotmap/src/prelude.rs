@@ -1,3 +1,7 @@
 pub use super::Slotmap;
 pub use super::SlotKey;
+pub use super::Key;
+pub use super::GrowthPolicy;
+pub use super::AllocationPolicy;
+pub use super::SecondarySlotmap;
 pub use create_custom_key;
\ No newline at end of file
@@ -0,0 +1,174 @@
+use std::marker::PhantomData;
+
+use super::{Generation, Key, SlotKey, Slotmap};
+
+struct SecondarySlot<V> {
+    generation: Generation,
+    value: V,
+}
+
+/// Stores optional data for the keys of a primary `Slotmap` without adding it to the primary values.\
+/// The slot generation is stored with each value, so keys that were removed from the primary slotmap and
+/// had their slot reused are treated as absent
+pub struct SecondarySlotmap<V, K: Key = SlotKey> {
+    slots: Vec<Option<SecondarySlot<V>>>,
+    len: usize,
+    key_type: PhantomData<K>,
+}
+
+impl<V, K: Key> Default for SecondarySlotmap<V, K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<V, K: Key> SecondarySlotmap<V, K> {
+    pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+
+    /// The capacity should match the capacity of the primary slotmap to avoid resizing when inserting
+    pub fn with_capacity(capacity: usize) -> Self {
+        let mut slots = Vec::with_capacity(capacity);
+        slots.resize_with(capacity, || None);
+        Self {
+            slots,
+            len: 0,
+            key_type: PhantomData,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Inserts a value for the key, returning the value previously stored for the same key.\
+    /// Values stored for an older generation of the slot are dropped. If the stored value belongs to a newer
+    /// generation the key is stale, so nothing is inserted and `None` is returned
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let key = key.slot_key();
+        if key.index >= self.slots.len() {
+            self.slots.resize_with(key.index + 1, || None);
+        }
+
+        let slot = &mut self.slots[key.index];
+        match slot {
+            Some(current) if current.generation > key.generation => None,
+            Some(current) if current.generation == key.generation => {
+                Some(std::mem::replace(&mut current.value, value))
+            }
+            Some(current) => {
+                *current = SecondarySlot {
+                    generation: key.generation,
+                    value,
+                };
+                None
+            }
+            None => {
+                *slot = Some(SecondarySlot {
+                    generation: key.generation,
+                    value,
+                });
+                self.len += 1;
+                None
+            }
+        }
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.get(key).is_some()
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V> {
+        let key = key.slot_key();
+        match self.slots.get(key.index) {
+            Some(Some(slot)) if slot.generation == key.generation => Some(&slot.value),
+            _ => None,
+        }
+    }
+
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        let key = key.slot_key();
+        match self.slots.get_mut(key.index) {
+            Some(Some(slot)) if slot.generation == key.generation => Some(&mut slot.value),
+            _ => None,
+        }
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        if !self.contains_key(key) {
+            return None;
+        }
+        self.len -= 1;
+        self.slots[key.slot_key().index].take().map(|slot| slot.value)
+    }
+
+    pub fn clear(&mut self) {
+        self.slots.iter_mut().for_each(|slot| *slot = None);
+        self.len = 0;
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (K, &V)> + '_ {
+        self.slots.iter().enumerate().filter_map(|(index, slot)| {
+            slot.as_ref().map(|slot| {
+                let key = K::from_slot_key(SlotKey {
+                    index,
+                    generation: slot.generation,
+                });
+                (key, &slot.value)
+            })
+        })
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (K, &mut V)> + '_ {
+        self.slots.iter_mut().enumerate().filter_map(|(index, slot)| {
+            slot.as_mut().map(|slot| {
+                let key = K::from_slot_key(SlotKey {
+                    index,
+                    generation: slot.generation,
+                });
+                (key, &mut slot.value)
+            })
+        })
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = K> + '_ {
+        self.iter().map(|(key, _)| key)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &V> + '_ {
+        self.iter().map(|(_, value)| value)
+    }
+
+    /// Removes all the values for which the function returns `false`
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(K, &mut V) -> bool,
+    {
+        for (index, slot) in self.slots.iter_mut().enumerate() {
+            let keep = match slot {
+                Some(current) => {
+                    let key = K::from_slot_key(SlotKey {
+                        index,
+                        generation: current.generation,
+                    });
+                    f(key, &mut current.value)
+                }
+                None => continue,
+            };
+            if !keep {
+                *slot = None;
+                self.len -= 1;
+            }
+        }
+    }
+
+    /// Removes all the values whose keys are no longer valid in the primary slotmap
+    pub fn retain_live<P>(&mut self, primary: &Slotmap<P, K>) {
+        self.retain(|key, _| primary.is_valid(&key));
+    }
+}
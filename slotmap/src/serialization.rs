@@ -0,0 +1,68 @@
+use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+use std::marker::PhantomData;
+
+use super::{AllocationPolicy, FreeList, Generation, GrowthPolicy, Key, Slot, SlotIndex, Slotmap};
+
+/// The slotmap is stored with its slot table and free list, so the generations are preserved and
+/// the `SlotKey`s that were created before serializing are still valid after deserializing
+#[derive(Serialize)]
+struct SlotmapRef<'a, V> {
+    values: &'a Vec<V>,
+    values_slot: &'a Vec<SlotIndex>,
+    slots: &'a Vec<Slot>,
+    free_list: &'a FreeList,
+    growth_policy: GrowthPolicy,
+    allocation_policy: AllocationPolicy,
+    generation_floor: Generation,
+}
+
+#[derive(Deserialize)]
+struct SlotmapData<V> {
+    values: Vec<V>,
+    values_slot: Vec<SlotIndex>,
+    slots: Vec<Slot>,
+    free_list: FreeList,
+    growth_policy: GrowthPolicy,
+    #[serde(default)]
+    allocation_policy: AllocationPolicy,
+    #[serde(default)]
+    generation_floor: Generation,
+}
+
+impl<V: Serialize, K: Key> Serialize for Slotmap<V, K> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        SlotmapRef {
+            values: &self.values,
+            values_slot: &self.values_slot,
+            slots: &self.slots,
+            free_list: &self.free_list,
+            growth_policy: self.growth_policy,
+            allocation_policy: self.free_list.policy(),
+            generation_floor: self.generation_floor,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de, V: Deserialize<'de>, K: Key> Deserialize<'de> for Slotmap<V, K> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let data = SlotmapData::<V>::deserialize(deserializer)?;
+        let mut slotmap = Slotmap {
+            values: data.values,
+            values_slot: data.values_slot,
+            slots: data.slots,
+            free_list: data.free_list,
+            growth_policy: data.growth_policy,
+            generation_floor: data.generation_floor,
+            key_type: PhantomData,
+        };
+        slotmap.free_list.set_policy(data.allocation_policy);
+        slotmap.free_list.reserve(slotmap.slots.len());
+        // The deserialized data must not make the slotmap index out of bounds or hand out a taken slot
+        if let Err(errors) = slotmap.validate() {
+            return Err(D::Error::custom(&errors[0]));
+        }
+        Ok(slotmap)
+    }
+}
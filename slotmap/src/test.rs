@@ -1,6 +1,14 @@
 #[cfg(test)]
 mod tests {
-    use crate::slotmap::slotmap::{SlotKey, Slotmap};
+    use crate::{AllocationPolicy, Generation, GrowthPolicy, Key, SecondarySlotmap, SlotKey, Slotmap, ValidationError};
+
+    /// Pushes the values in order and returns their keys
+    fn push_values<K: Key>(slotmap: &mut Slotmap<u32, K>, values: impl IntoIterator<Item = u32>) -> Vec<K> {
+        values
+            .into_iter()
+            .map(|value| slotmap.push(value).expect("Could not push value"))
+            .collect()
+    }
 
     #[test]
     fn single_value_can_be_pushed_into_empty_slotmap() {
@@ -57,11 +65,8 @@ mod tests {
                     2,
                     "Free List does not have the correct lenght"
                 );
-                match u32_slotmap.get_value(&slot_keys[0]) {
-                    Some(_) => {
-                        panic!("Removing a value should make the key invalid")
-                    }
-                    None => {}
+                if u32_slotmap.get_value(&slot_keys[0]).is_some() {
+                    panic!("Removing a value should make the key invalid")
                 }
             }
             None => {
@@ -218,15 +223,13 @@ mod tests {
     fn values_cannot_be_pushed_to_slotmaps_at_capacity(){
         let mut u32_slotmap = Slotmap::<u32>::with_capacity(100);
         for i in 0..100 {
-            if let Some(_) = u32_slotmap.push(i) {
-            } else {
+            if u32_slotmap.push(i).is_none() {
                 panic!("Could not push value")
             }
         }
         assert_eq!(u32_slotmap.capacity(), 100, "The capacity is not correct");
-        match u32_slotmap.push(20) {
-            Some(_) => panic!("No value should be pushed if the slot map is full"),
-            None => {}
+        if u32_slotmap.push(20).is_some() {
+            panic!("No value should be pushed if the slot map is full")
         }
     }
 
@@ -234,8 +237,7 @@ mod tests {
     fn capacity_of_a_full_slotmap_can_be_increased(){
         let mut u32_slotmap = Slotmap::<u32>::with_capacity(100);
         for i in 0..100 {
-            if let Some(_) = u32_slotmap.push(i) {
-            } else {
+            if u32_slotmap.push(i).is_none() {
                 panic!("Could not push value")
             }
         }
@@ -259,8 +261,7 @@ mod tests {
     fn when_the_capacity_is_increased_the_resulting_bucket_is_merged_with_the_current_tail_if_possible(){
         let mut u32_slotmap = Slotmap::<u32>::with_capacity(100);
         for i in 0..90 {
-            if let Some(_) = u32_slotmap.push(i) {
-            } else {
+            if u32_slotmap.push(i).is_none() {
                 panic!("Could not push value")
             }
         }
@@ -268,11 +269,8 @@ mod tests {
         assert_eq!(u32_slotmap.capacity(), 100, "The capacity is not correct");
         assert_eq!(u32_slotmap.len(), 90, "All objects were not pushed");
 
-        match u32_slotmap.reserve_exact(10) {
-            Some(_) => {
-                panic!("Capacity should not have been increased")
-            },
-            None => {},
+        if u32_slotmap.reserve_exact(10).is_some() {
+            panic!("Capacity should not have been increased")
         }
 
         match u32_slotmap.reserve_exact(20) {
@@ -304,8 +302,8 @@ mod tests {
         assert_eq!(u32_slotmap.len(), 100, "The length is not correct");
         assert_eq!(u32_slotmap.free_list_slice().len(), 0, "There should not be free slots");
 
-        for i in 0 as usize .. 20{
-            u32_slotmap.remove(slot_keys[i]);
+        for key in &slot_keys[..20] {
+            u32_slotmap.remove(*key);
         }
         let free_list_slice = u32_slotmap.free_list_slice();
         assert_eq!(free_list_slice.len(), 1, "There should be a single free bucket");
@@ -328,4 +326,663 @@ mod tests {
             assert_eq!(free_list_slice[index], expected, "Free list does not have the correct structure");
         }
     }
+
+    #[test]
+    fn full_slotmap_with_double_growth_policy_doubles_its_capacity_on_push(){
+        let mut u32_slotmap = Slotmap::<u32>::with_growth_policy(10, GrowthPolicy::Double);
+        let slot_keys = push_values(&mut u32_slotmap, 0..25);
+        assert_eq!(u32_slotmap.capacity(), 40, "The capacity is not correct");
+        assert_eq!(u32_slotmap.len(), 25, "All objects were not pushed");
+        assert_eq!(u32_slotmap.free_list_slice(), vec![(25, 40)], "Free list does not have the correct structure");
+
+        for (index, key) in slot_keys.iter().enumerate() {
+            assert_eq!(u32_slotmap.get_value(key), Some(&(index as u32)), "The stored value is not the correct one");
+        }
+    }
+
+    #[test]
+    fn empty_slotmap_with_double_growth_policy_can_grow(){
+        let mut u32_slotmap = Slotmap::<u32>::with_growth_policy(0, GrowthPolicy::Double);
+        push_values(&mut u32_slotmap, 0..5);
+        assert_eq!(u32_slotmap.capacity(), 8, "The capacity is not correct");
+    }
+
+    #[test]
+    fn full_slotmap_with_increment_growth_policy_grows_by_the_increment(){
+        let mut u32_slotmap = Slotmap::<u32>::with_growth_policy(10, GrowthPolicy::Increment(7));
+        push_values(&mut u32_slotmap, 0..18);
+        assert_eq!(u32_slotmap.capacity(), 24, "The capacity is not correct");
+        assert_eq!(u32_slotmap.free_list_slice(), vec![(18, 24)], "Free list does not have the correct structure");
+    }
+
+    #[test]
+    fn full_slotmap_with_capped_growth_policy_stops_growing_at_the_cap(){
+        let mut u32_slotmap = Slotmap::<u32>::with_growth_policy(10, GrowthPolicy::Capped(25));
+        push_values(&mut u32_slotmap, 0..25);
+        assert_eq!(u32_slotmap.capacity(), 25, "The capacity should not be bigger than the cap");
+        if u32_slotmap.push(25).is_some() {
+            panic!("No value should be pushed if the slotmap is at the cap")
+        }
+    }
+
+    #[test]
+    fn growth_reuses_free_slots_before_the_new_ones(){
+        let mut u32_slotmap = Slotmap::<u32>::with_growth_policy(4, GrowthPolicy::Fixed);
+        let slot_keys = push_values(&mut u32_slotmap, 0..4);
+        u32_slotmap.remove(slot_keys[1]);
+        u32_slotmap.set_growth_policy(GrowthPolicy::Double);
+
+        u32_slotmap.push(10).expect("Could not push value");
+        assert_eq!(u32_slotmap.capacity(), 4, "Capacity should not grow while there are free slots");
+
+        u32_slotmap.push(11).expect("Could not push value");
+        assert_eq!(u32_slotmap.capacity(), 8, "The capacity is not correct");
+        assert_eq!(u32_slotmap.free_list_slice(), vec![(5, 8)], "Free list does not have the correct structure");
+    }
+
+    #[test]
+    fn a_removed_value_that_does_not_touch_any_free_bucket_creates_a_new_bucket_in_order(){
+        let mut u32_slotmap = Slotmap::<u32>::with_capacity(20);
+        let slot_keys = push_values(&mut u32_slotmap, 0..20);
+
+        u32_slotmap.remove(slot_keys[10]);
+        u32_slotmap.remove(slot_keys[2]);
+        u32_slotmap.remove(slot_keys[15]);
+        u32_slotmap.remove(slot_keys[6]);
+        assert_eq!(u32_slotmap.free_list_slice(), vec![(2, 3), (6, 7), (10, 11), (15, 16)], "Free list does not have the correct structure");
+
+        u32_slotmap.remove(slot_keys[5]);
+        u32_slotmap.remove(slot_keys[11]);
+        u32_slotmap.remove(slot_keys[19]);
+        assert_eq!(u32_slotmap.free_list_slice(), vec![(2, 3), (5, 7), (10, 12), (15, 16), (19, 20)], "Free list does not have the correct structure");
+    }
+
+    #[test]
+    fn free_slots_are_taken_from_the_lowest_bucket_first(){
+        let mut u32_slotmap = Slotmap::<u32>::with_capacity(20);
+        let slot_keys = push_values(&mut u32_slotmap, 0..20);
+
+        u32_slotmap.remove(slot_keys[12]);
+        u32_slotmap.remove(slot_keys[4]);
+        u32_slotmap.remove(slot_keys[3]);
+
+        u32_slotmap.push(20).expect("Could not push value");
+        assert_eq!(u32_slotmap.free_list_slice(), vec![(4, 5), (12, 13)], "Free list does not have the correct structure");
+        u32_slotmap.push(21).expect("Could not push value");
+        u32_slotmap.push(22).expect("Could not push value");
+        assert_eq!(u32_slotmap.free_list_len(), 0, "There should not be free slots");
+        if u32_slotmap.push(23).is_some() {
+            panic!("No value should be pushed if the slot map is full")
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn a_slotmap_with_removed_and_reused_slots_keeps_its_keys_valid_after_serialization(){
+        let mut u32_slotmap = Slotmap::<u32>::with_growth_policy(10, GrowthPolicy::Double);
+        let mut slot_keys = push_values(&mut u32_slotmap, 0..10);
+        let removed_keys = [slot_keys.remove(7), slot_keys.remove(3), slot_keys.remove(0)];
+        for key in removed_keys {
+            u32_slotmap.remove(key);
+        }
+        //Slot 0 is reused, so its generation is increased
+        slot_keys.push(u32_slotmap.push(20).expect("Could not push value"));
+
+        let json = serde_json::to_string(&u32_slotmap).expect("Slotmap could not be serialized");
+        let keys_json = serde_json::to_string(&slot_keys).expect("Keys could not be serialized");
+
+        let mut loaded_slotmap: Slotmap<u32> = serde_json::from_str(&json).expect("Slotmap could not be deserialized");
+        let loaded_keys: Vec<SlotKey> = serde_json::from_str(&keys_json).expect("Keys could not be deserialized");
+
+        assert_eq!(loaded_keys, slot_keys, "Keys changed after serialization");
+        assert_eq!(loaded_slotmap.len(), u32_slotmap.len(), "The length is not correct");
+        assert_eq!(loaded_slotmap.capacity(), u32_slotmap.capacity(), "The capacity is not correct");
+        assert_eq!(loaded_slotmap.growth_policy(), GrowthPolicy::Double, "The growth policy is not correct");
+        assert_eq!(loaded_slotmap.free_list_slice(), vec![(3, 4), (7, 8)], "Free list does not have the correct structure");
+
+        for key in loaded_keys.iter() {
+            assert_eq!(loaded_slotmap.get_value(key), u32_slotmap.get_value(key), "The stored value is not the correct one");
+        }
+        for key in removed_keys.iter() {
+            if loaded_slotmap.get_value(key).is_some() {
+                panic!("Removed keys should stay invalid after deserialization")
+            }
+        }
+
+        let new_key = loaded_slotmap.push(30).expect("Could not push value");
+        assert_eq!(loaded_slotmap.get_value(&new_key), Some(&30));
+        if loaded_slotmap.get_value(&removed_keys[1]).is_some() {
+            panic!("A reused slot should not make the removed key valid")
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn inconsistent_serialized_slotmaps_are_rejected(){
+        let mut u32_slotmap = Slotmap::<u32>::with_capacity(4);
+        push_values(&mut u32_slotmap, 0..2);
+        let mut json: serde_json::Value = serde_json::to_value(&u32_slotmap).expect("Slotmap could not be serialized");
+        json["values_slot"][1] = serde_json::json!(3);
+
+        if serde_json::from_value::<Slotmap<u32>>(json).is_ok() {
+            panic!("A value pointing to a free slot should not be deserialized")
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serialized_taken_slots_with_the_max_generation_are_rejected(){
+        let mut u32_slotmap = Slotmap::<u32>::with_capacity(4);
+        let key = u32_slotmap.push(1).expect("Could not push value");
+        let mut json: serde_json::Value = serde_json::to_value(&u32_slotmap).expect("Slotmap could not be serialized");
+        json["slots"][key.index]["generation"] = serde_json::json!(Generation::MAX);
+
+        //Removing the value would overflow the generation of the slot
+        let error = serde_json::from_value::<Slotmap<u32>>(json)
+            .err()
+            .expect("A taken slot with the max generation should not be deserialized");
+        assert_eq!(error.to_string(), ValidationError::RetiredTakenSlot { slot_index: key.index }.to_string());
+    }
+
+    #[test]
+    fn secondary_slotmap_stores_values_for_primary_keys(){
+        let mut u32_slotmap = Slotmap::<u32>::with_capacity(10);
+        let mut names = SecondarySlotmap::<String>::with_capacity(10);
+        let slot_keys = push_values(&mut u32_slotmap, 0..10);
+
+        names.insert(slot_keys[2], String::from("two"));
+        names.insert(slot_keys[5], String::from("five"));
+        assert_eq!(names.len(), 2, "The length is not correct");
+        assert_eq!(names.get(&slot_keys[2]).map(String::as_str), Some("two"));
+        assert_eq!(names.get(&slot_keys[3]), None, "Keys without value should be absent");
+
+        let previous = names.insert(slot_keys[2], String::from("TWO"));
+        assert_eq!(previous.as_deref(), Some("two"), "The previous value should be returned");
+        assert_eq!(names.len(), 2, "Replacing a value should not change the length");
+
+        let mut iterated: Vec<(SlotKey, String)> = names.iter().map(|(key, name)| (key, name.clone())).collect();
+        iterated.sort_by_key(|(_, name)| name.clone());
+        assert_eq!(iterated, vec![(slot_keys[2], String::from("TWO")), (slot_keys[5], String::from("five"))]);
+
+        assert_eq!(names.remove(&slot_keys[5]).as_deref(), Some("five"));
+        assert_eq!(names.len(), 1, "The length is not correct");
+        assert_eq!(names.remove(&slot_keys[5]), None, "A value cannot be removed twice");
+    }
+
+    #[test]
+    fn secondary_slotmap_treats_stale_keys_as_absent(){
+        let mut u32_slotmap = Slotmap::<u32>::with_capacity(4);
+        let mut flags = SecondarySlotmap::<bool>::new();
+        let old_key = u32_slotmap.push(0).expect("Could not push value");
+        flags.insert(old_key, true);
+
+        u32_slotmap.remove(old_key);
+        let new_key = u32_slotmap.push(1).expect("Could not push value");
+        assert_eq!(u32_slotmap.get_value(&new_key), Some(&1));
+
+        assert_eq!(flags.get(&new_key), None, "The value of the old key should not be visible with the new key");
+        flags.insert(new_key, false);
+        assert_eq!(flags.get(&old_key), None, "Stale keys should be absent");
+        assert_eq!(flags.get(&new_key), Some(&false));
+        assert_eq!(flags.len(), 1, "The stale value should have been replaced");
+
+        assert_eq!(flags.insert(old_key, true), None);
+        assert_eq!(flags.get(&new_key), Some(&false), "Stale keys should not replace newer values");
+    }
+
+    #[test]
+    fn secondary_slotmap_can_retain_only_the_keys_live_in_the_primary(){
+        let mut u32_slotmap = Slotmap::<u32>::with_capacity(10);
+        let mut doubled = SecondarySlotmap::<u32>::with_capacity(10);
+        let slot_keys = push_values(&mut u32_slotmap, 0..10);
+        for (i, key) in slot_keys.iter().enumerate() {
+            doubled.insert(*key, i as u32 * 2);
+        }
+
+        for key in slot_keys.iter().step_by(3) {
+            u32_slotmap.remove(*key);
+        }
+        doubled.retain_live(&u32_slotmap);
+
+        assert_eq!(doubled.len(), 6, "The length is not correct");
+        for key in slot_keys.iter() {
+            assert_eq!(doubled.contains_key(key), u32_slotmap.is_valid(key));
+        }
+
+        doubled.retain(|_, value| *value > 10);
+        let mut values: Vec<u32> = doubled.values().copied().collect();
+        values.sort();
+        assert_eq!(values, vec![14, 16]);
+    }
+
+    #[test]
+    fn iterating_with_keys_returns_the_key_of_each_value(){
+        let mut u32_slotmap = Slotmap::<u32>::with_capacity(20);
+        let slot_keys = push_values(&mut u32_slotmap, 0..20);
+        for key in slot_keys.iter().step_by(4) {
+            u32_slotmap.remove(*key);
+        }
+
+        assert_eq!(u32_slotmap.iter().len(), 15, "The iterator length is not correct");
+        for ((key, value), expected_value) in u32_slotmap.iter().zip(u32_slotmap.get_iter()) {
+            assert_eq!(value, expected_value, "Values should be iterated in the same order as get_iter");
+            assert_eq!(u32_slotmap.get_value(&key), Some(value), "The key does not point to the value");
+            assert_eq!(key, slot_keys[*value as usize], "The key is not the one returned by push");
+        }
+
+        for (key, value) in u32_slotmap.iter_mut() {
+            *value += key.index as u32;
+        }
+        for (key, value) in u32_slotmap.iter() {
+            assert_eq!(*value, 2 * key.index as u32, "The value was not modified");
+        }
+
+        let keys: Vec<SlotKey> = u32_slotmap.keys().collect();
+        let iter_keys: Vec<SlotKey> = u32_slotmap.iter().map(|(key, _)| key).collect();
+        assert_eq!(keys, iter_keys, "Keys should be in the same order as the values");
+    }
+
+    #[test]
+    fn retain_removes_the_values_rejected_by_the_function(){
+        let mut u32_slotmap = Slotmap::<u32>::with_capacity(20);
+        let slot_keys = push_values(&mut u32_slotmap, 0..20);
+
+        let mut visited = 0;
+        u32_slotmap.retain(|key, value| {
+            visited += 1;
+            assert_eq!(key, slot_keys[*value as usize], "The key is not the one returned by push");
+            *value % 3 != 0
+        });
+
+        assert_eq!(visited, 20, "Every value should be visited once");
+        assert_eq!(u32_slotmap.len(), 13, "The length is not correct");
+        for (i, key) in slot_keys.iter().enumerate() {
+            assert_eq!(u32_slotmap.is_valid(key), i % 3 != 0, "Only rejected values should be removed");
+        }
+        assert_eq!(u32_slotmap.free_list_len(), 7, "Free list does not have the correct lenght");
+    }
+
+    #[test]
+    fn clear_makes_all_keys_invalid_and_frees_all_slots(){
+        let mut u32_slotmap = Slotmap::<u32>::with_capacity(10);
+        let slot_keys = push_values(&mut u32_slotmap, 0..10);
+        u32_slotmap.remove(slot_keys[4]);
+
+        u32_slotmap.clear();
+        assert!(u32_slotmap.is_empty(), "Slotmap should be empty");
+        assert_eq!(u32_slotmap.capacity(), 10, "The capacity should not change");
+        assert_eq!(u32_slotmap.free_list_slice(), vec![(0, 10)], "All slots should be free");
+
+        for i in 0..10 {
+            let key = u32_slotmap.push(i).expect("Could not push value");
+            if slot_keys.contains(&key) {
+                panic!("Keys created before clear should not be created again")
+            }
+        }
+        for key in slot_keys.iter() {
+            if u32_slotmap.get_value(key).is_some() {
+                panic!("Keys created before clear should be invalid")
+            }
+        }
+    }
+
+    #[test]
+    fn drain_returns_all_values_with_their_keys(){
+        let mut u32_slotmap = Slotmap::<u32>::with_capacity(10);
+        let slot_keys = push_values(&mut u32_slotmap, 0..10);
+        u32_slotmap.remove(slot_keys[0]);
+
+        let mut drained: Vec<(SlotKey, u32)> = u32_slotmap.drain().collect();
+        drained.sort_by_key(|(_, value)| *value);
+        let expected: Vec<(SlotKey, u32)> = (1..10).map(|i| (slot_keys[i as usize], i)).collect();
+        assert_eq!(drained, expected, "Drained values are not correct");
+
+        assert!(u32_slotmap.is_empty(), "Slotmap should be empty");
+        assert_eq!(u32_slotmap.free_list_slice(), vec![(0, 10)], "All slots should be free");
+        for (key, _) in drained.iter() {
+            if u32_slotmap.get_value(key).is_some() {
+                panic!("Drained keys should be invalid")
+            }
+        }
+
+        u32_slotmap.push(3).expect("Could not push value");
+        drop(u32_slotmap.drain().take(0));
+        assert!(u32_slotmap.is_empty(), "Values that were not consumed should be dropped");
+    }
+
+    create_custom_key!(TextureKey;);
+
+    #[test]
+    fn slotmaps_with_custom_keys_return_and_accept_the_custom_key(){
+        let mut u32_slotmap = Slotmap::<u32, TextureKey>::with_capacity(10);
+        let mut names = SecondarySlotmap::<&str, TextureKey>::new();
+        let texture_keys = push_values(&mut u32_slotmap, 0..10);
+        names.insert(texture_keys[3], "three");
+
+        assert_eq!(u32_slotmap.get_value(&texture_keys[3]), Some(&3));
+        assert_eq!(names.get(&texture_keys[3]), Some(&"three"));
+        for (key, value) in u32_slotmap.iter() {
+            assert_eq!(key, texture_keys[*value as usize], "The key is not the one returned by push");
+        }
+
+        assert_eq!(u32_slotmap.remove(texture_keys[3]), Some(3));
+        names.retain_live(&u32_slotmap);
+        assert!(names.is_empty(), "The removed key should not be live");
+
+        let slot_key: SlotKey = texture_keys[4].slot_key();
+        assert_eq!(TextureKey::from_slot_key(slot_key), texture_keys[4]);
+    }
+
+    #[test]
+    fn keys_out_of_range_are_not_valid(){
+        let mut big_slotmap = Slotmap::<u32>::with_capacity(100);
+        let mut small_slotmap = Slotmap::<u32>::with_capacity(10);
+        let big_key = push_values(&mut big_slotmap, 0..100)[99];
+        small_slotmap.push(0).expect("Could not push value");
+
+        assert!(!small_slotmap.is_valid(&big_key), "A key out of range should not be valid");
+        assert_eq!(small_slotmap.get_value(&big_key), None);
+        assert_eq!(small_slotmap.get_value_mut(&big_key), None);
+        assert_eq!(small_slotmap.remove(big_key), None);
+        assert_eq!(small_slotmap.len(), 1, "Nothing should be removed");
+    }
+
+    #[test]
+    fn slots_are_retired_when_the_generation_reaches_the_max(){
+        let mut u32_slotmap = Slotmap::<u32>::with_capacity(4);
+        u32_slotmap.slots[0].generation = Generation::MAX - 1;
+        let retiring_key = u32_slotmap.push(0).expect("Could not push value");
+        assert_eq!(retiring_key.index, 0, "The first free slot should be used");
+
+        assert_eq!(u32_slotmap.remove(retiring_key), Some(0));
+        assert_eq!(u32_slotmap.free_list_slice(), vec![(1, 4)], "The retired slot should not be free");
+        assert!(u32_slotmap.validate().is_ok(), "Retired slots are consistent");
+
+        for i in 0..3 {
+            let key = u32_slotmap.push(i).expect("Could not push value");
+            assert_ne!(key.index, 0, "The retired slot should not be reused");
+        }
+        if u32_slotmap.push(3).is_some() {
+            panic!("The slotmap cannot grow and its only free slot is retired")
+        }
+
+        u32_slotmap.clear();
+        assert_eq!(u32_slotmap.free_list_slice(), vec![(1, 4)], "Clear should not free the retired slot");
+        assert!(u32_slotmap.validate().is_ok(), "Retired slots are consistent after clear");
+    }
+
+    #[test]
+    fn validate_reports_inconsistencies(){
+        let mut u32_slotmap = Slotmap::<u32>::with_capacity(10);
+        let slot_keys = push_values(&mut u32_slotmap, 0..5);
+        u32_slotmap.remove(slot_keys[2]);
+        assert_eq!(u32_slotmap.validate(), Ok(()));
+
+        u32_slotmap.slots[slot_keys[2].index].taken = true;
+        let errors = u32_slotmap.validate().expect_err("The tampered slotmap should not be valid");
+        assert!(errors.contains(&ValidationError::TakenSlotCount { taken_slots: 5, values: 4 }));
+        assert!(errors.contains(&ValidationError::UnavailableFreeSlot { slot_index: slot_keys[2].index }));
+
+        u32_slotmap.slots[slot_keys[2].index].taken = false;
+        u32_slotmap.slots[slot_keys[0].index].index = 3;
+        let errors = u32_slotmap.validate().expect_err("The tampered slotmap should not be valid");
+        assert!(errors.contains(&ValidationError::ValueSlotMismatch { value_index: 0, slot_index: slot_keys[0].index }));
+    }
+
+    #[test]
+    fn get_disjoint_mut_returns_all_values_for_distinct_valid_keys(){
+        let mut u32_slotmap = Slotmap::<u32>::with_capacity(10);
+        let slot_keys = push_values(&mut u32_slotmap, 0..10);
+
+        match u32_slotmap.get_disjoint_mut([slot_keys[2], slot_keys[7], slot_keys[4]]) {
+            Some([a, b, c]) => {
+                std::mem::swap(a, b);
+                *c += 10;
+            }
+            None => panic!("Distinct valid keys should return all the values"),
+        }
+        assert_eq!(u32_slotmap.get_value(&slot_keys[2]), Some(&7));
+        assert_eq!(u32_slotmap.get_value(&slot_keys[7]), Some(&2));
+        assert_eq!(u32_slotmap.get_value(&slot_keys[4]), Some(&14));
+
+        if u32_slotmap.get_disjoint_mut([slot_keys[1], slot_keys[1]]).is_some() {
+            panic!("Repeated keys should not return values")
+        }
+        u32_slotmap.remove(slot_keys[3]);
+        if u32_slotmap.get_disjoint_mut([slot_keys[1], slot_keys[3]]).is_some() {
+            panic!("Invalid keys should not return values")
+        }
+
+        let [a, b] = unsafe { u32_slotmap.get_disjoint_unchecked_mut([slot_keys[0], slot_keys[9]]) };
+        *a += *b;
+        assert_eq!(u32_slotmap.get_value(&slot_keys[0]), Some(&9));
+    }
+
+    #[test]
+    fn insert_with_key_gives_the_value_its_own_key(){
+        let mut key_slotmap = Slotmap::<SlotKey>::with_growth_policy(2, GrowthPolicy::Double);
+        let mut slot_keys = Vec::<SlotKey>::with_capacity(10);
+        for _ in 0..10 {
+            slot_keys.push(key_slotmap.insert_with_key(|key| key).expect("Could not insert value"));
+        }
+        for key in slot_keys.iter() {
+            assert_eq!(key_slotmap.get_value(key), Some(key), "The value should be its own key");
+        }
+        assert!(key_slotmap.validate().is_ok());
+    }
+
+    #[test]
+    fn vacant_entries_can_be_filled_later_or_dropped(){
+        let mut u32_slotmap = Slotmap::<u32>::with_capacity(2);
+
+        let entry = u32_slotmap.vacant_entry().expect("There should be a free slot");
+        let entry_key = entry.key();
+        assert_eq!(entry.insert(5), entry_key, "The inserted key should be the one handed out");
+        assert_eq!(u32_slotmap.get_value(&entry_key), Some(&5));
+
+        let entry = u32_slotmap.vacant_entry().expect("There should be a free slot");
+        let dropped_key = entry.key();
+        drop(entry);
+        assert!(!u32_slotmap.is_valid(&dropped_key), "The key of a dropped entry should not be valid");
+        assert_eq!(u32_slotmap.free_list_len(), 1, "The slot of a dropped entry should be free");
+
+        let reused_key = u32_slotmap.push(6).expect("Could not push value");
+        assert_eq!(reused_key.index, dropped_key.index, "The slot should be reused");
+        assert!(!u32_slotmap.is_valid(&dropped_key), "The key of a dropped entry should stay invalid");
+        if u32_slotmap.vacant_entry().is_some() {
+            panic!("A full slotmap that cannot grow should not create entries")
+        }
+        assert!(u32_slotmap.validate().is_ok());
+    }
+
+    #[test]
+    fn slotmaps_can_be_sent_and_shared_between_threads(){
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Slotmap<u32>>();
+        assert_send_sync::<SecondarySlotmap<u32>>();
+
+        let mut u32_slotmap = Slotmap::<u32>::with_capacity(10);
+        let key = u32_slotmap.push(7).expect("Could not push value");
+        let value = std::thread::spawn(move || *u32_slotmap.get_value(&key).unwrap())
+            .join()
+            .expect("The thread panicked");
+        assert_eq!(value, 7);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn par_iter_visits_every_value_with_its_key(){
+        use rayon::prelude::*;
+
+        let mut u32_slotmap = Slotmap::<u32>::with_capacity(1000);
+        let slot_keys = push_values(&mut u32_slotmap, 0..1000);
+        for key in slot_keys.iter().step_by(3) {
+            u32_slotmap.remove(*key);
+        }
+
+        u32_slotmap.par_iter_mut().for_each(|(_, value)| *value *= 2);
+        let mut pairs: Vec<(SlotKey, u32)> = u32_slotmap.par_iter().map(|(key, value)| (key, *value)).collect();
+        pairs.sort_by_key(|(_, value)| *value);
+
+        let expected: Vec<(SlotKey, u32)> = (0..1000)
+            .filter(|i| i % 3 != 0)
+            .map(|i| (slot_keys[i as usize], i * 2))
+            .collect();
+        assert_eq!(pairs, expected, "The parallel iterator should pair every value with its key");
+    }
+
+    #[test]
+    fn remove_ordered_keeps_the_order_of_the_values(){
+        let mut u32_slotmap = Slotmap::<u32>::with_capacity(10);
+        let slot_keys = push_values(&mut u32_slotmap, 0..10);
+
+        assert_eq!(u32_slotmap.remove_ordered(slot_keys[2]), Some(2));
+        assert_eq!(u32_slotmap.remove_ordered(slot_keys[0]), Some(0));
+        assert_eq!(u32_slotmap.remove_ordered(slot_keys[9]), Some(9));
+        assert_eq!(u32_slotmap.remove_ordered(slot_keys[9]), None, "The key should be invalid after removing it");
+
+        let values: Vec<u32> = u32_slotmap.get_iter().copied().collect();
+        assert_eq!(values, vec![1, 3, 4, 5, 6, 7, 8], "The order of the values should not change");
+        for i in [1, 3, 4, 5, 6, 7, 8] {
+            assert_eq!(u32_slotmap.get_value(&slot_keys[i]), Some(&(i as u32)));
+        }
+        assert_eq!(u32_slotmap.free_list_slice(), vec![(0, 1), (2, 3), (9, 10)]);
+        assert!(u32_slotmap.validate().is_ok());
+    }
+
+    #[test]
+    fn sort_by_keeps_keys_valid(){
+        let mut u32_slotmap = Slotmap::<u32>::with_capacity(10);
+        let slot_keys = push_values(&mut u32_slotmap, [5, 3, 8, 1, 9, 0, 2, 7, 4, 6]);
+        u32_slotmap.remove(slot_keys[4]);
+
+        u32_slotmap.sort_by(|a, b| a.cmp(b));
+        let values: Vec<u32> = u32_slotmap.get_iter().copied().collect();
+        assert_eq!(values, vec![0, 1, 2, 3, 4, 5, 6, 7, 8], "The values should be sorted");
+        for (key, value) in u32_slotmap.iter() {
+            assert_eq!(u32_slotmap.get_value(&key), Some(value));
+        }
+        assert_eq!(u32_slotmap.get_value(&slot_keys[0]), Some(&5));
+
+        u32_slotmap.sort_by_key(|value| std::cmp::Reverse(*value));
+        let values: Vec<u32> = u32_slotmap.get_iter().copied().collect();
+        assert_eq!(values, vec![8, 7, 6, 5, 4, 3, 2, 1, 0], "The values should be sorted in reverse");
+        assert_eq!(u32_slotmap.get_value(&slot_keys[2]), Some(&8));
+        assert!(u32_slotmap.validate().is_ok());
+    }
+
+    #[test]
+    fn sort_by_survives_a_panicking_comparator(){
+        let mut u32_slotmap = Slotmap::<u32>::with_capacity(10);
+        let slot_keys = push_values(&mut u32_slotmap, [5, 3, 8, 1, 9, 0, 2, 7, 4, 6]);
+        let capacity = u32_slotmap.values.capacity();
+
+        let mut comparisons = 0;
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            u32_slotmap.sort_by(|a, b| {
+                comparisons += 1;
+                if comparisons == 5 {
+                    panic!("Comparator panicked");
+                }
+                a.cmp(b)
+            });
+        }));
+        assert!(result.is_err(), "The comparator should have panicked");
+
+        assert!(u32_slotmap.validate().is_ok(), "The slotmap should stay consistent after the panic");
+        assert_eq!(u32_slotmap.len(), 10);
+        assert_eq!(u32_slotmap.values.capacity(), capacity, "The capacity of the values should be kept");
+        for (key, value) in slot_keys.iter().zip([5, 3, 8, 1, 9, 0, 2, 7, 4, 6]) {
+            assert_eq!(u32_slotmap.get_value(key), Some(&value));
+        }
+
+        u32_slotmap.sort_by(|a, b| a.cmp(b));
+        assert_eq!(u32_slotmap.values.capacity(), capacity, "Sorting should not reallocate the values");
+        assert!(u32_slotmap.validate().is_ok());
+    }
+
+    #[test]
+    fn allocation_policies_choose_the_reused_slot(){
+        let policies = [
+            (AllocationPolicy::LowestIndexFirst, [1, 3, 5]),
+            (AllocationPolicy::Lifo, [1, 3, 5]),
+            (AllocationPolicy::Fifo, [5, 3, 1]),
+        ];
+        for (policy, expected_indices) in policies {
+            let mut u32_slotmap = Slotmap::<u32>::with_capacity(6);
+            let slot_keys = push_values(&mut u32_slotmap, 0..6);
+            u32_slotmap.set_allocation_policy(policy);
+            assert_eq!(u32_slotmap.allocation_policy(), policy);
+
+            for i in [5, 3, 1] {
+                u32_slotmap.remove(slot_keys[i]);
+            }
+            for expected_index in expected_indices {
+                let key = u32_slotmap.push(0).expect("Could not push value");
+                assert_eq!(key.index, expected_index, "{:?} did not use the expected slot", policy);
+                assert!(u32_slotmap.validate().is_ok(), "{:?} left the slotmap inconsistent", policy);
+            }
+        }
+    }
+
+    #[test]
+    fn fifo_uses_slots_added_when_growing_after_the_freed_slots(){
+        let mut u32_slotmap = Slotmap::<u32>::with_growth_policy(2, GrowthPolicy::Double);
+        u32_slotmap.set_allocation_policy(AllocationPolicy::Fifo);
+        let first_key = u32_slotmap.push(0).expect("Could not push value");
+        u32_slotmap.push(1).expect("Could not push value");
+        u32_slotmap.push(2).expect("Could not push value");
+        u32_slotmap.remove(first_key);
+
+        let indices: Vec<usize> = (0..2).map(|i| u32_slotmap.push(i).expect("Could not push value").index).collect();
+        assert_eq!(indices, vec![3, 0], "The slot added when growing was freed before the removed slot");
+        assert!(u32_slotmap.validate().is_ok());
+    }
+
+    #[test]
+    fn lifo_can_take_a_slot_from_the_middle_of_a_bucket(){
+        let mut u32_slotmap = Slotmap::<u32>::with_capacity(5);
+        u32_slotmap.set_allocation_policy(AllocationPolicy::Lifo);
+        let slot_keys = push_values(&mut u32_slotmap, 0..5);
+        for i in [1, 3, 2] {
+            u32_slotmap.remove(slot_keys[i]);
+        }
+        assert_eq!(u32_slotmap.free_list_slice(), vec![(1, 4)]);
+
+        assert_eq!(u32_slotmap.push(0).expect("Could not push value").index, 2);
+        assert_eq!(u32_slotmap.free_list_slice(), vec![(1, 2), (3, 4)], "The bucket should be split");
+        assert!(u32_slotmap.validate().is_ok());
+    }
+
+    #[test]
+    fn shrink_to_fit_removes_trailing_free_slots(){
+        let mut u32_slotmap = Slotmap::<u32>::with_capacity(100);
+        let slot_keys = push_values(&mut u32_slotmap, 0..100);
+        for key in slot_keys[10..].iter() {
+            u32_slotmap.remove(*key);
+        }
+        u32_slotmap.remove(slot_keys[5]);
+
+        assert_eq!(u32_slotmap.shrink_to_fit(), 90, "All the trailing free slots should be removed");
+        assert_eq!(u32_slotmap.capacity(), 10);
+        assert_eq!(u32_slotmap.free_list_slice(), vec![(5, 6)], "Free slots before the last taken slot are kept");
+        assert!(u32_slotmap.validate().is_ok());
+        for i in (0..10).filter(|i| *i != 5) {
+            assert_eq!(u32_slotmap.get_value(&slot_keys[i]), Some(&(i as u32)));
+        }
+
+        u32_slotmap.reserve_exact(100);
+        for key in slot_keys[10..].iter() {
+            if u32_slotmap.is_valid(key) {
+                panic!("Keys of removed slots should not become valid after growing again")
+            }
+        }
+        for _ in 0..100 {
+            let key = u32_slotmap.push(0).expect("Could not push value");
+            if slot_keys.contains(&key) {
+                panic!("Keys of removed slots should not be handed out again")
+            }
+        }
+        assert!(u32_slotmap.validate().is_ok());
+    }
 }
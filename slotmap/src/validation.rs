@@ -0,0 +1,164 @@
+use std::fmt;
+
+use super::{Generation, Key, SlotIndex, Slotmap, ValueIndex};
+
+/// An inconsistency between the values, the slot table and the free list of a slotmap
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidationError {
+    /// `values` and `values_slot` do not have the same length
+    ValueSlotLength { values: usize, values_slot: usize },
+    /// The value points to a slot that does not exist, is not taken or points to another value
+    ValueSlotMismatch { value_index: ValueIndex, slot_index: SlotIndex },
+    /// The slot is taken but its generation is `Generation::MAX`, removing its value would overflow the generation
+    RetiredTakenSlot { slot_index: SlotIndex },
+    /// The amount of taken slots is not the same as the amount of values
+    TakenSlotCount { taken_slots: usize, values: usize },
+    /// The free bucket is empty, out of range, or not sorted after the previous bucket
+    InvalidFreeBucket { start: SlotIndex, end: SlotIndex },
+    /// A slot in the free list is taken or retired
+    UnavailableFreeSlot { slot_index: SlotIndex },
+    /// Some slots are not taken, free or retired
+    LostSlots { count: usize },
+    /// The order in which the slots were freed does not contain the same slots as the free list
+    FreeOrderMismatch { ordered_slots: usize, free_slots: usize },
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationError::ValueSlotLength { values, values_slot } => write!(
+                f,
+                "There are {} values but {} value slots",
+                values, values_slot
+            ),
+            ValidationError::ValueSlotMismatch {
+                value_index,
+                slot_index,
+            } => write!(
+                f,
+                "Value {} points to slot {} which does not point back to it",
+                value_index, slot_index
+            ),
+            ValidationError::RetiredTakenSlot { slot_index } => write!(
+                f,
+                "Slot {} is taken but its generation is already the maximum",
+                slot_index
+            ),
+            ValidationError::TakenSlotCount {
+                taken_slots,
+                values,
+            } => write!(
+                f,
+                "There are {} taken slots but {} values",
+                taken_slots, values
+            ),
+            ValidationError::InvalidFreeBucket { start, end } => {
+                write!(f, "Free bucket {}..{} is not valid", start, end)
+            }
+            ValidationError::UnavailableFreeSlot { slot_index } => write!(
+                f,
+                "Slot {} is in the free list but it is taken or retired",
+                slot_index
+            ),
+            ValidationError::LostSlots { count } => {
+                write!(f, "{} slots are not taken, free or retired", count)
+            }
+            ValidationError::FreeOrderMismatch {
+                ordered_slots,
+                free_slots,
+            } => write!(
+                f,
+                "The free order has {} slots but there are {} free slots",
+                ordered_slots, free_slots
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+impl<V, K: Key> Slotmap<V, K> {
+    /// Checks that the values, slot table and free list agree with each other, returning every inconsistency found.\
+    /// This goes through all the slots, so it is meant for debugging and for checking data that was loaded
+    pub fn validate(&self) -> Result<(), Vec<ValidationError>> {
+        let mut errors = Vec::new();
+
+        if self.values.len() != self.values_slot.len() {
+            errors.push(ValidationError::ValueSlotLength {
+                values: self.values.len(),
+                values_slot: self.values_slot.len(),
+            });
+        }
+
+        for (value_index, slot_index) in self.values_slot.iter().enumerate() {
+            match self.slots.get(*slot_index) {
+                Some(slot) if slot.taken && slot.index == value_index => {}
+                _ => errors.push(ValidationError::ValueSlotMismatch {
+                    value_index,
+                    slot_index: *slot_index,
+                }),
+            }
+        }
+
+        for (slot_index, slot) in self.slots.iter().enumerate() {
+            if slot.taken && slot.generation == Generation::MAX {
+                errors.push(ValidationError::RetiredTakenSlot { slot_index });
+            }
+        }
+
+        let taken_slots = self.slots.iter().filter(|slot| slot.taken).count();
+        if taken_slots != self.values.len() {
+            errors.push(ValidationError::TakenSlotCount {
+                taken_slots,
+                values: self.values.len(),
+            });
+        }
+
+        let retired_slots = self
+            .slots
+            .iter()
+            .filter(|slot| !slot.taken && slot.generation == Generation::MAX)
+            .count();
+
+        let mut free_slots = 0;
+        let mut prev_end = None;
+        for (start, end) in self.free_list.create_list_slice() {
+            let sorted = prev_end.map_or(true, |prev_end| prev_end < start);
+            if start >= end || end > self.slots.len() || !sorted {
+                errors.push(ValidationError::InvalidFreeBucket { start, end });
+                continue;
+            }
+            for slot_index in start..end {
+                let slot = &self.slots[slot_index];
+                if slot.taken || slot.generation == Generation::MAX {
+                    errors.push(ValidationError::UnavailableFreeSlot { slot_index });
+                }
+            }
+            free_slots += end - start;
+            prev_end = Some(end);
+        }
+
+        if let Some(order) = self.free_list.order() {
+            let all_free = order.iter().all(|slot_index| self.free_list.contains(*slot_index));
+            if order.len() != free_slots || !all_free {
+                errors.push(ValidationError::FreeOrderMismatch {
+                    ordered_slots: order.len(),
+                    free_slots,
+                });
+            }
+        }
+
+        let accounted_slots = free_slots + taken_slots + retired_slots;
+        if accounted_slots < self.slots.len() {
+            errors.push(ValidationError::LostSlots {
+                count: self.slots.len() - accounted_slots,
+            });
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
@@ -4,7 +4,10 @@ use std::{
     time::Instant,
 };
 
-use super::time::{FrameNumber, Microsecond, Millisecond, Second};
+use super::{
+    fixed_timestep::FixedTimestep,
+    time::{FrameNumber, Microsecond, Millisecond, Second},
+};
 
 #[repr(C)]
 #[derive(Default, Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
@@ -26,6 +29,8 @@ pub struct EngineTimer {
     pub time_since_start: Microsecond,
     /// Expected frame duration
     pub frame_duration: Microsecond,
+    /// Decides how many times `Runtime::fixed_update` runs each frame, the step is the frame duration by default
+    pub fixed_timestep: FixedTimestep,
 }
 impl EngineTimer {
     /// Create a timer for the system.
@@ -33,6 +38,11 @@ impl EngineTimer {
     /// If the update and render takes longer, then the update for the next frame is started right after the prev one
     pub fn new(frame_duration: Microsecond, render_system: &Graphics) -> Self {
         let time_data = TimeBufferData::default();
+        let fixed_step = if frame_duration.0 > 0 {
+            frame_duration
+        } else {
+            Microsecond(16_667)
+        };
 
         let time_buffer = render_system.create_buffer(
             "Engine Time",
@@ -45,6 +55,7 @@ impl EngineTimer {
             accumulated_time: Microsecond(0),
             time_since_start: Microsecond(0),
             frame_duration,
+            fixed_timestep: FixedTimestep::new(fixed_step, 5),
             time_data: time_data,
             time_buffer: time_buffer,
             last_render_time: std::time::Instant::now(),
@@ -55,6 +66,7 @@ impl EngineTimer {
         self.accumulated_time = Microsecond(0);
         self.time_since_start = Microsecond(0);
         self.last_render_time = std::time::Instant::now();
+        self.fixed_timestep.reset();
     }
 
     /// Returns TRUE if the the system should update
@@ -65,15 +77,8 @@ impl EngineTimer {
 
         if self.accumulated_time >= self.frame_duration {
             //println!("Last Frame Time {}", self.accumulated_time);
-            self.frame_count += FrameNumber(1);
             self.last_render_time = now;
-            self.time_since_start += self.accumulated_time;
-
-            self.time_data.time_millis = self.time_since_start.as_millisecond();
-            self.time_data.delta_time_milis = self.accumulated_time.as_millisecond();
-
-            self.time_data.time = self.time_since_start.as_seconds();
-            self.time_data.delta_time = self.accumulated_time.as_seconds();
+            self.advance_frame(self.accumulated_time);
 
             self.accumulated_time = Microsecond(0);
             return true;
@@ -81,6 +86,21 @@ impl EngineTimer {
         return false;
     }
 
+    /// Starts a new frame that lasted `frame_time` without looking at the clock.\
+    /// Used to run frames with a simulated time, e.g. by the headless loop
+    pub fn advance_frame(&mut self, frame_time: Microsecond) {
+        self.frame_count += FrameNumber(1);
+        self.time_since_start += frame_time;
+
+        self.time_data.time_millis = self.time_since_start.as_millisecond();
+        self.time_data.delta_time_milis = frame_time.as_millisecond();
+
+        self.time_data.time = self.time_since_start.as_seconds();
+        self.time_data.delta_time = frame_time.as_seconds();
+
+        self.fixed_timestep.advance(frame_time);
+    }
+
     pub fn update_buffer(&mut self, queue: &wgpu::Queue) {
         queue.write_buffer(&self.time_buffer, 0, bytemuck::bytes_of(&[self.time_data]));
     }
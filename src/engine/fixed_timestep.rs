@@ -0,0 +1,63 @@
+use super::time::{Microsecond, Second};
+
+/// Accumulates the frame time and splits it into fixed steps, so simulation code does not depend on the frame rate.\
+/// The time left in the accumulator is used to interpolate the rendered state between the last two steps
+pub struct FixedTimestep {
+    /// Simulated time of a single step
+    pub step: Microsecond,
+    /// Most steps that run in a single frame, the time that would need more steps is dropped so a slow frame does
+    /// not make the next frames slower (spiral of death)
+    pub max_steps_per_frame: u32,
+    accumulated_time: Microsecond,
+    steps: u32,
+}
+
+impl FixedTimestep {
+    pub fn new(step: Microsecond, max_steps_per_frame: u32) -> Self {
+        assert!(step.0 > 0, "The fixed time step cannot be 0");
+        Self {
+            step,
+            max_steps_per_frame,
+            accumulated_time: Microsecond(0),
+            steps: 0,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.accumulated_time = Microsecond(0);
+        self.steps = 0;
+    }
+
+    /// Adds the frame time to the accumulator and returns the number of steps that have to run this frame
+    pub fn advance(&mut self, frame_time: Microsecond) -> u32 {
+        let accumulated_time = self.accumulated_time.0 + frame_time.0;
+        let steps = accumulated_time / self.step.0;
+        let max_steps = self.max_steps_per_frame as u128;
+        self.steps = steps.min(max_steps) as u32;
+        //If the steps were clamped only the remainder of a step is kept
+        self.accumulated_time = Microsecond(accumulated_time % self.step.0);
+        self.steps
+    }
+
+    /// Number of steps returned by the last `advance`
+    pub fn steps(&self) -> u32 {
+        self.steps
+    }
+
+    /// Calls `fixed_update` once for each step of the current frame, the frame loop runs `Runtime::fixed_update` with it
+    pub fn run_steps(&self, mut fixed_update: impl FnMut()) {
+        for _ in 0..self.steps {
+            fixed_update();
+        }
+    }
+
+    pub fn step_seconds(&self) -> Second {
+        self.step.as_seconds()
+    }
+
+    /// How far the current frame is between the last step and the next one, in the range [0, 1).\
+    /// Rendering `lerp(previous_state, current_state, alpha)` hides the difference between the step and frame rates
+    pub fn interpolation_alpha(&self) -> f32 {
+        self.accumulated_time.0 as f32 / self.step.0 as f32
+    }
+}
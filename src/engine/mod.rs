@@ -7,8 +7,9 @@ use winit::{
 pub mod time;
 use time::*;
 pub mod engine_timer;
+pub mod fixed_timestep;
 pub mod operation_timer;
-use crate::graphics;
+use crate::graphics::{self, GraphicsError};
 use operation_timer::*;
 
 pub struct Engine {
@@ -23,6 +24,17 @@ pub struct Engine {
 impl Engine {
     pub fn new(window: &Window, frame_time_micros: Microsecond) -> Self {
         let render_system = pollster::block_on(graphics::Graphics::new(&window));
+        Self::from_graphics(render_system, frame_time_micros)
+    }
+
+    /// Creates an engine without a window that renders to a texture of the given size.\
+    /// The frames are run with `HeadlessLoop` instead of `start_engine_loop`
+    pub fn new_headless(size: UVec2, frame_time_micros: Microsecond) -> Result<Self, GraphicsError> {
+        let render_system = pollster::block_on(graphics::Graphics::new_headless(size))?;
+        Ok(Self::from_graphics(render_system, frame_time_micros))
+    }
+
+    fn from_graphics(render_system: graphics::Graphics, frame_time_micros: Microsecond) -> Self {
         let engine_time = engine_timer::EngineTimer::new(frame_time_micros, &render_system);
 
         let (system_bind_group_layout, system_bind_group) = render_system.create_bind_group(
@@ -56,6 +68,9 @@ impl Engine {
     }
 
     pub fn get_screen_size(&self) -> UVec2 {
-        self.graphics.render_window.size
+        self.graphics.render_target.size()
     }
 }
+
+#[cfg(test)]
+mod test;
@@ -4,6 +4,7 @@ use super::time::Microsecond;
 pub struct OperationTimer {
     pub frame_start_time: Microsecond,
     pub event_handling_time: Microsecond,
+    pub fixed_update_time: Microsecond,
     pub update_time: Microsecond,
     pub render_time: Microsecond,
 	pub frame_end_time: Microsecond,
@@ -18,6 +19,7 @@ impl OperationTimer {
 	pub fn copy_from(&mut self, other: &Self){
 		self.frame_start_time = other.frame_start_time;
 		self.event_handling_time = other.event_handling_time;
+		self.fixed_update_time = other.fixed_update_time;
 		self.update_time = other.update_time;
 		self.render_time = other.render_time;
 		self.frame_end_time = other.frame_end_time;
@@ -25,7 +27,7 @@ impl OperationTimer {
 	}
 
 	pub fn get_total_time(&self) -> Microsecond{
-		let time =(self.frame_start_time + self.event_handling_time + self.update_time + self.render_time + self.frame_end_time);
+		let time =(self.frame_start_time + self.event_handling_time + self.fixed_update_time + self.update_time + self.render_time + self.frame_end_time);
 		time
 	}
 }
@@ -0,0 +1,161 @@
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use glam::uvec2;
+    use winit::window::WindowId;
+
+    use crate::{
+        engine::{fixed_timestep::FixedTimestep, time::Microsecond},
+        graphics::frame_capture::ImageSequence,
+        Engine, EngineEvent, HeadlessLoop, Runtime,
+    };
+
+    #[test]
+    fn frame_time_is_split_into_fixed_steps(){
+        let mut timestep = FixedTimestep::new(Microsecond(10_000), 5);
+        assert_eq!(timestep.advance(Microsecond(4_000)), 0);
+        assert_eq!(timestep.interpolation_alpha(), 0.4);
+
+        assert_eq!(timestep.advance(Microsecond(21_000)), 2);
+        assert_eq!(timestep.steps(), 2);
+        assert_eq!(timestep.interpolation_alpha(), 0.5);
+
+        timestep.reset();
+        assert_eq!(timestep.interpolation_alpha(), 0.0);
+        assert_eq!(timestep.steps(), 0);
+    }
+
+    #[test]
+    fn slow_frames_are_clamped_to_the_max_steps(){
+        let mut timestep = FixedTimestep::new(Microsecond(10_000), 3);
+        assert_eq!(timestep.advance(Microsecond(1_002_000)), 3);
+        //The time of the steps that did not run is dropped
+        assert_eq!(timestep.interpolation_alpha(), 0.2);
+        assert_eq!(timestep.advance(Microsecond(8_000)), 1);
+    }
+
+    #[test]
+    fn frames_run_the_fixed_updates_of_their_steps(){
+        let mut timestep = FixedTimestep::new(Microsecond(10_000), 5);
+        let mut fixed_updates = Vec::new();
+        //Frames faster, equal and slower than the step, the last one is clamped to the max steps
+        for frame_time in [4_000, 4_000, 4_000, 10_000, 25_000, 75_000] {
+            timestep.advance(Microsecond(frame_time));
+            let mut steps = 0;
+            timestep.run_steps(|| steps += 1);
+            fixed_updates.push(steps);
+        }
+        assert_eq!(fixed_updates, vec![0, 0, 1, 1, 2, 5]);
+        assert_eq!(timestep.interpolation_alpha(), 0.2);
+
+        timestep.reset();
+        timestep.run_steps(|| panic!("No steps run after a reset"));
+    }
+
+    #[derive(Default)]
+    struct CountingRuntime {
+        fixed_updates: u32,
+        rendered_frames: u32,
+        exit_after: u32,
+    }
+
+    impl Runtime for CountingRuntime {
+        fn get_window_id(&self) -> WindowId {
+            unsafe { WindowId::dummy() }
+        }
+
+        fn frame_start(&mut self, _engine: &Engine) {}
+
+        fn handle_event_queue<F>(&mut self, _event_queue: &VecDeque<EngineEvent>, _engine: &mut Engine, _exit_event_loop: &mut F)
+        where
+            F: FnMut(),
+        {
+        }
+
+        fn fixed_update(&mut self, _engine: &Engine, _exit_event_loop: &mut dyn FnMut()) {
+            self.fixed_updates += 1;
+        }
+
+        fn update(&mut self, _engine: &Engine, _exit_event_loop: &mut dyn FnMut()) {}
+
+        fn render(&mut self, _engine: &Engine, screen_view: &wgpu::TextureView, encoder: &mut wgpu::CommandEncoder) {
+            encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Clear"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: screen_view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::RED),
+                        store: true,
+                    },
+                })],
+                depth_stencil_attachment: None,
+            });
+        }
+
+        fn frame_end<F>(&mut self, _engine: &mut Engine, exit_event_loop: &mut F)
+        where
+            F: FnMut(),
+        {
+            self.rendered_frames += 1;
+            if self.rendered_frames == self.exit_after {
+                exit_event_loop();
+            }
+        }
+
+        fn before_exit(&mut self, _engine: &Engine) {}
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter, run with --ignored"]
+    fn headless_engine_runs_frames_with_a_simulated_delta(){
+        let mut engine = Engine::new_headless(uvec2(64, 32), Microsecond(16_000)).unwrap();
+        assert_eq!(engine.get_screen_size(), uvec2(64, 32));
+        engine.timer.fixed_timestep.step = Microsecond(8_000);
+
+        let mut runtime = CountingRuntime {
+            exit_after: 10,
+            ..Default::default()
+        };
+        let mut headless = HeadlessLoop::new(&mut engine, Microsecond(16_000));
+        assert_eq!(headless.run_frames(&mut engine, &mut runtime, 4).unwrap(), 4);
+        assert_eq!(runtime.rendered_frames, 4);
+        assert_eq!(runtime.fixed_updates, 8);
+        assert_eq!(engine.timer.time_since_start.0, 64_000);
+
+        //The runtime asks to exit after the 10th frame
+        assert_eq!(headless.run_frames(&mut engine, &mut runtime, 100).unwrap(), 6);
+        assert!(headless.exit_requested());
+        assert_eq!(runtime.rendered_frames, 10);
+
+        engine.graphics.resize(uvec2(16, 16));
+        assert_eq!(engine.get_screen_size(), uvec2(16, 16));
+        headless.exit(&engine, &mut runtime);
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter, run with --ignored"]
+    fn headless_frames_are_captured_and_recorded_as_png(){
+        let mut engine = Engine::new_headless(uvec2(70, 3), Microsecond(16_000)).unwrap();
+        let mut runtime = CountingRuntime::default();
+        let mut headless = HeadlessLoop::new(&mut engine, Microsecond(16_000));
+        headless.step(&mut engine, &mut runtime).unwrap();
+
+        //The runtime clears the frame to red, the rows of 70 pixels are padded to 512 bytes in the buffer
+        let frame = engine.graphics.capture_render_target().unwrap();
+        assert_eq!(frame.size, uvec2(70, 3));
+        assert_eq!(frame.rgba.len(), 70 * 3 * 4);
+        assert_eq!(frame.pixel(0, 0), [255, 0, 0, 255]);
+        assert_eq!(frame.pixel(69, 2), [255, 0, 0, 255]);
+
+        let directory = std::env::temp_dir().join(format!("rwge_capture_{}", std::process::id()));
+        let mut image_sequence = ImageSequence::new(&directory, "frame").unwrap();
+        assert_eq!(headless.record_frames(&mut engine, &mut runtime, 3, &mut image_sequence).unwrap(), 3);
+        assert_eq!(image_sequence.next_frame(), 3);
+        assert_eq!(engine.timer.time_since_start.0, 4 * 16_000);
+        let last_frame = std::fs::read(directory.join("frame_00002.png")).unwrap();
+        assert_eq!(last_frame, frame.to_png().unwrap());
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+}
@@ -0,0 +1,195 @@
+use std::sync::Mutex;
+
+use super::{public_data::PublicDataKey, resource::Resource, Component, Entity, World};
+
+/// The commands receive the entities spawned by the queue so far
+type CommandFunction = Box<dyn FnOnce(&mut World, &mut Vec<Entity>) + Send>;
+
+#[derive(Clone, Copy)]
+enum EntityTarget {
+    Existing(Entity),
+    /// Index of the spawn command, the entity does not exist until the commands are applied
+    Spawned(usize),
+}
+
+impl EntityTarget {
+    fn resolve(&self, spawned: &[Entity]) -> Entity {
+        match *self {
+            EntityTarget::Existing(entity) => entity,
+            EntityTarget::Spawned(index) => spawned[index],
+        }
+    }
+}
+
+#[derive(Default)]
+struct CommandQueue {
+    commands: Vec<CommandFunction>,
+    spawn_count: usize,
+}
+
+impl CommandQueue {
+    fn apply(self, world: &mut World) {
+        let mut spawned = Vec::with_capacity(self.spawn_count);
+        for command in self.commands {
+            command(world, &mut spawned);
+        }
+    }
+}
+
+/// Queue of changes to the world that are applied later, so they can be requested while the world is being iterated
+/// or by systems running in parallel.\
+/// When a stage finishes, the commands are applied in the order of the systems in the schedule, and the commands of
+/// each system in the order the system added them
+#[derive(Default)]
+pub struct Commands {
+    queue: Mutex<CommandQueue>,
+}
+
+impl Commands {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, CommandQueue> {
+        self.queue.lock().expect("Command queue was poisoned")
+    }
+
+    fn push_entity_command<F>(&self, target: EntityTarget, command: F)
+    where
+        F: FnOnce(&mut World, Entity) + Send + 'static,
+    {
+        self.lock()
+            .commands
+            .push(Box::new(move |world, spawned| command(world, target.resolve(spawned))));
+    }
+
+    /// Adds a command that runs with exclusive access to the world
+    pub fn add<F>(&self, command: F)
+    where
+        F: FnOnce(&mut World) + Send + 'static,
+    {
+        self.lock().commands.push(Box::new(move |world, _| command(world)));
+    }
+
+    /// The entity is spawned when the commands are applied, the commands added through the returned value
+    /// run on the new entity
+    pub fn spawn(&self) -> EntityCommands<'_> {
+        let mut queue = self.lock();
+        let spawn_index = queue.spawn_count;
+        queue.spawn_count += 1;
+        queue
+            .commands
+            .push(Box::new(|world, spawned| spawned.push(world.spawn())));
+        EntityCommands {
+            commands: self,
+            target: EntityTarget::Spawned(spawn_index),
+        }
+    }
+
+    pub fn entity(&self, entity: Entity) -> EntityCommands<'_> {
+        EntityCommands {
+            commands: self,
+            target: EntityTarget::Existing(entity),
+        }
+    }
+
+    pub fn despawn(&self, entity: Entity) {
+        self.entity(entity).despawn();
+    }
+
+    /// Creates an entry in the public data of the world, the function receives its key so it can be stored.\
+    /// The `Created` event is added to the public data event queue when the commands are applied
+    pub fn create_public_data<T, F>(&self, data: T, on_created: F)
+    where
+        T: Send + Sync + 'static,
+        F: FnOnce(&mut World, PublicDataKey<T>) + Send + 'static,
+    {
+        self.add(move |world| {
+            if let Some(key) = world.public_data_mut().create(data) {
+                on_created(world, key);
+            }
+        });
+    }
+
+    pub fn insert_resource<T: Resource>(&self, resource: T) {
+        self.add(move |world| {
+            world.insert_resource(resource);
+        });
+    }
+
+    pub fn remove_resource<T: Resource>(&self) {
+        self.add(|world| {
+            world.remove_resource::<T>();
+        });
+    }
+
+    pub fn len(&self) -> usize {
+        self.lock().commands.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn take_queue(&mut self) -> CommandQueue {
+        std::mem::take(
+            self.queue
+                .get_mut()
+                .expect("Command queue was poisoned"),
+        )
+    }
+
+    /// Moves the commands of `other` to the end of the queue, they are added as a single command that keeps its
+    /// own spawned entities
+    pub(super) fn append(&mut self, other: &mut Commands) {
+        let other = other.take_queue();
+        if !other.commands.is_empty() {
+            self.add(move |world| other.apply(world));
+        }
+    }
+
+    /// Runs all the commands in the order they were added, and empties the queue
+    pub fn apply(&mut self, world: &mut World) {
+        self.take_queue().apply(world);
+    }
+}
+
+/// Commands for a single entity, which can be an entity that is going to be spawned
+pub struct EntityCommands<'c> {
+    commands: &'c Commands,
+    target: EntityTarget,
+}
+
+impl EntityCommands<'_> {
+    pub fn insert<T: Component>(self, component: T) -> Self {
+        self.commands
+            .push_entity_command(self.target, move |world, entity| {
+                world.insert(entity, component);
+            });
+        self
+    }
+
+    pub fn remove<T: Component>(self) -> Self {
+        self.commands
+            .push_entity_command(self.target, |world, entity| {
+                world.remove::<T>(entity);
+            });
+        self
+    }
+
+    /// Runs the function with the entity once it exists, e.g. to store its key in another component
+    pub fn then<F>(self, function: F) -> Self
+    where
+        F: FnOnce(&mut World, Entity) + Send + 'static,
+    {
+        self.commands.push_entity_command(self.target, function);
+        self
+    }
+
+    pub fn despawn(self) {
+        self.commands
+            .push_entity_command(self.target, |world, entity| {
+                world.despawn(entity);
+            });
+    }
+}
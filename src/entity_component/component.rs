@@ -0,0 +1,269 @@
+use std::any::Any;
+
+use crate::slotmap::SecondarySlotmap;
+
+use super::Entity;
+
+/// Any type that can be shared between threads can be used as a component
+pub trait Component: Send + Sync + 'static {}
+impl<T: Send + Sync + 'static> Component for T {}
+
+/// Change ticks of a component, the tick of the world is increased after each system runs and by
+/// `World::clear_trackers`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ComponentTicks {
+    pub added: u32,
+    pub changed: u32,
+}
+
+impl ComponentTicks {
+    /// Returns `true` if the component was inserted after the observer last ran
+    pub fn is_added(&self, last_run_tick: u32, change_tick: u32) -> bool {
+        is_newer_tick(self.added, last_run_tick, change_tick)
+    }
+
+    /// Returns `true` if the component was inserted or accessed mutably after the observer last ran
+    pub fn is_changed(&self, last_run_tick: u32, change_tick: u32) -> bool {
+        is_newer_tick(self.changed, last_run_tick, change_tick)
+    }
+}
+
+/// Returns `true` if `tick` is newer than `last_run_tick`.\
+/// The ticks are compared by how old they are relative to the current tick, so the comparison keeps working after
+/// the tick wraps around
+pub(super) fn is_newer_tick(tick: u32, last_run_tick: u32, change_tick: u32) -> bool {
+    change_tick.wrapping_sub(tick) < change_tick.wrapping_sub(last_run_tick)
+}
+
+/// Sparse set that stores the components of a single type densely, so queries iterate over a packed `Vec`.\
+/// The position of the component of each entity is stored in a `SecondarySlotmap`, which makes the components of
+/// despawned entities unreachable even if the storage was not updated.\
+/// Every insertion and mutable access stamps the component with the current tick, and the removed entities are kept
+/// with the tick they were removed at until every observer has seen them, so the changes since an observer last ran
+/// can be found without comparing the components
+pub struct ComponentStorage<T> {
+    components: Vec<T>,
+    entities: Vec<Entity>,
+    ticks: Vec<ComponentTicks>,
+    indices: SecondarySlotmap<usize, Entity>,
+    removed: Vec<(Entity, u32)>,
+    tick: u32,
+    /// Tick of the current observer, the changes newer than it are reported by `is_added`, `is_changed` and `removed`
+    last_run_tick: u32,
+}
+
+impl<T: Component> Default for ComponentStorage<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Component> ComponentStorage<T> {
+    pub fn new() -> Self {
+        Self {
+            components: Vec::new(),
+            entities: Vec::new(),
+            ticks: Vec::new(),
+            indices: SecondarySlotmap::new(),
+            removed: Vec::new(),
+            tick: 0,
+            last_run_tick: 0,
+        }
+    }
+
+    pub(super) fn with_change_ticks(tick: u32, last_run_tick: u32) -> Self {
+        Self {
+            tick,
+            last_run_tick,
+            ..Self::new()
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.components.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.components.is_empty()
+    }
+
+    /// The entities that have this component, in the same order as the components
+    pub fn entities(&self) -> &[Entity] {
+        &self.entities
+    }
+
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.indices.contains_key(&entity)
+    }
+
+    /// Returns the component the entity had before, replacing a component counts as a change but not as an addition.\
+    /// If a newer generation of the entity is already stored the entity is dead, so the component is dropped
+    pub(super) fn insert(&mut self, entity: Entity, component: T) -> Option<T> {
+        if let Some(index) = self.indices.get(&entity) {
+            self.ticks[*index].changed = self.tick;
+            return Some(std::mem::replace(&mut self.components[*index], component));
+        }
+        let index = self.components.len();
+        self.indices.insert(entity, index);
+        //The index is not stored for stale keys, pushing the component would desync the dense and sparse storages
+        if self.indices.get(&entity) != Some(&index) {
+            return None;
+        }
+        self.components.push(component);
+        self.entities.push(entity);
+        self.ticks.push(ComponentTicks {
+            added: self.tick,
+            changed: self.tick,
+        });
+        None
+    }
+
+    pub fn remove(&mut self, entity: Entity) -> Option<T> {
+        let index = self.indices.remove(&entity)?;
+        let component = self.components.swap_remove(index);
+        self.entities.swap_remove(index);
+        self.ticks.swap_remove(index);
+        self.removed.push((entity, self.tick));
+        //The last component was moved into the removed position
+        if let Some(moved_entity) = self.entities.get(index) {
+            *self
+                .indices
+                .get_mut(moved_entity)
+                .expect("Moved component does not have an index") = index;
+        }
+        Some(component)
+    }
+
+    pub fn get(&self, entity: Entity) -> Option<&T> {
+        let index = self.indices.get(&entity)?;
+        Some(&self.components[*index])
+    }
+
+    /// Marks the component as changed, even if it is not modified
+    pub fn get_mut(&mut self, entity: Entity) -> Option<&mut T> {
+        let index = self.indices.get(&entity)?;
+        self.ticks[*index].changed = self.tick;
+        Some(&mut self.components[*index])
+    }
+
+    pub fn ticks(&self, entity: Entity) -> Option<ComponentTicks> {
+        let index = self.indices.get(&entity)?;
+        Some(self.ticks[*index])
+    }
+
+    /// Returns `true` if the component was inserted since the current system last ran,
+    /// or since the trackers were cleared outside of the systems
+    pub fn is_added(&self, entity: Entity) -> bool {
+        self.ticks(entity)
+            .is_some_and(|ticks| ticks.is_added(self.last_run_tick, self.tick))
+    }
+
+    /// Returns `true` if the component was inserted or accessed mutably since the current system last ran,
+    /// or since the trackers were cleared outside of the systems
+    pub fn is_changed(&self, entity: Entity) -> bool {
+        self.ticks(entity)
+            .is_some_and(|ticks| ticks.is_changed(self.last_run_tick, self.tick))
+    }
+
+    /// The entities that lost the component since the current system last ran, including the despawned entities
+    pub fn removed(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.removed
+            .iter()
+            .filter(|(_, tick)| is_newer_tick(*tick, self.last_run_tick, self.tick))
+            .map(|(entity, _)| *entity)
+    }
+
+    /// Pointer to the component of the entity, used by queries that need access to several storages.\
+    /// Only the fields that are needed are borrowed, so the entity slice of the storage can be used at the same time
+    ///
+    /// # Safety
+    /// The storage pointer has to be valid and the component cannot be modified while the pointer is used
+    pub(super) unsafe fn component_ptr(storage: *const Self, entity: Entity) -> Option<*const T> {
+        let index = *(*storage).indices.get(&entity)?;
+        Some((*storage).components.as_ptr().add(index))
+    }
+
+    /// Same as `component_ptr`, but the component can be modified through the pointer.\
+    /// The component is not marked as changed, `mark_changed_ptr` has to be called if it is used
+    ///
+    /// # Safety
+    /// The storage pointer has to be valid, and the component cannot be aliased while the pointer is used
+    pub(super) unsafe fn component_ptr_mut(storage: *mut Self, entity: Entity) -> Option<*mut T> {
+        let index = *(*storage).indices.get(&entity)?;
+        //'as_mut_ptr' does not create a reference to the components, so other component references stay valid
+        Some((*storage).components.as_mut_ptr().add(index))
+    }
+
+    /// Marks the component of the entity as changed through a pointer, so the components can be borrowed meanwhile
+    ///
+    /// # Safety
+    /// The storage pointer has to be valid and the ticks of the entity cannot be accessed at the same time
+    pub(super) unsafe fn mark_changed_ptr(storage: *mut Self, entity: Entity) {
+        if let Some(&index) = (*storage).indices.get(&entity) {
+            (*(*storage).ticks.as_mut_ptr().add(index)).changed = (*storage).tick;
+        }
+    }
+
+    /// Same as `ticks`, but through a pointer so it can be used together with the pointers to the components
+    ///
+    /// # Safety
+    /// The storage pointer has to be valid and the ticks cannot be modified while they are read
+    pub(super) unsafe fn ticks_ptr(storage: *const Self, entity: Entity) -> Option<(ComponentTicks, u32)> {
+        let index = *(*storage).indices.get(&entity)?;
+        Some((*(*storage).ticks.as_ptr().add(index), (*storage).tick))
+    }
+
+    /// # Safety
+    /// The storage pointer has to be valid and the storage cannot be modified while the slice is used
+    pub(super) unsafe fn entities_slice<'a>(storage: *const Self) -> &'a [Entity] {
+        &(*storage).entities
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Entity, &T)> + '_ {
+        self.entities.iter().copied().zip(self.components.iter())
+    }
+
+    /// Marks all the components as changed
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (Entity, &mut T)> + '_ {
+        for ticks in self.ticks.iter_mut() {
+            ticks.changed = self.tick;
+        }
+        self.entities.iter().copied().zip(self.components.iter_mut())
+    }
+}
+
+/// Type erased component storage, so the world can store the storages of all the component types together
+pub(super) trait AnyStorage: Send + Sync {
+    fn contains_entity(&self, entity: Entity) -> bool;
+    fn remove_entity(&mut self, entity: Entity);
+    /// Sets the tick used by the next changes and the tick of the observer the changes are reported to
+    fn set_change_ticks(&mut self, tick: u32, last_run_tick: u32);
+    /// Forgets the removed entities that are not newer than the oldest observer
+    fn forget_removed(&mut self, oldest_last_run_tick: u32);
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<T: Component> AnyStorage for ComponentStorage<T> {
+    fn contains_entity(&self, entity: Entity) -> bool {
+        self.contains(entity)
+    }
+
+    fn remove_entity(&mut self, entity: Entity) {
+        self.remove(entity);
+    }
+
+    fn set_change_ticks(&mut self, tick: u32, last_run_tick: u32) {
+        self.tick = tick;
+        self.last_run_tick = last_run_tick;
+    }
+
+    fn forget_removed(&mut self, oldest_last_run_tick: u32) {
+        let tick = self.tick;
+        self.removed
+            .retain(|(_, removed_tick)| is_newer_tick(*removed_tick, oldest_last_run_tick, tick));
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
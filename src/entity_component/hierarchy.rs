@@ -0,0 +1,229 @@
+use std::fmt::Display;
+
+use glam::Vec2;
+
+use crate::gui::rect_ui::Rect;
+
+use super::{Entity, System, World};
+
+/// Position, rotation in radians and scale of an entity, relative to its parent
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transform2D {
+    pub position: Vec2,
+    pub rotation: f32,
+    pub scale: Vec2,
+}
+
+impl Default for Transform2D {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl Transform2D {
+    pub const IDENTITY: Self = Self {
+        position: Vec2::ZERO,
+        rotation: 0.0,
+        scale: Vec2::ONE,
+    };
+
+    pub fn from_position(position: Vec2) -> Self {
+        Self {
+            position,
+            ..Self::IDENTITY
+        }
+    }
+
+    /// Scales, rotates and then translates the point
+    pub fn transform_point(&self, point: Vec2) -> Vec2 {
+        let (sin, cos) = self.rotation.sin_cos();
+        let scaled = point * self.scale;
+        self.position + Vec2::new(scaled.x * cos - scaled.y * sin, scaled.x * sin + scaled.y * cos)
+    }
+
+    /// Places the child transform inside this one.\
+    /// The scales are multiplied component wise, so a rotated child of a non uniformly scaled parent is not skewed
+    pub fn mul_transform(&self, child: &Transform2D) -> Transform2D {
+        Transform2D {
+            position: self.transform_point(child.position),
+            rotation: self.rotation + child.rotation,
+            scale: self.scale * child.scale,
+        }
+    }
+}
+
+/// Transform of the entity relative to the world, computed by `World::propagate_transforms`
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct GlobalTransform2D(pub Transform2D);
+
+impl GlobalTransform2D {
+    pub fn transform(&self) -> &Transform2D {
+        &self.0
+    }
+
+    /// Moves a rect that is relative to the entity to the world, the rect position is its center
+    pub fn transform_rect(&self, rect: Rect) -> Rect {
+        Rect {
+            position: self.0.transform_point(rect.position),
+            size: rect.size * self.0.scale.abs(),
+        }
+    }
+}
+
+/// Parent of the entity, it is set by `World::set_parent`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Parent(Entity);
+
+impl Parent {
+    pub fn entity(&self) -> Entity {
+        self.0
+    }
+}
+
+/// Children of the entity in the order they were added, it is updated by `World::set_parent`
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Children(Vec<Entity>);
+
+impl Children {
+    pub fn entities(&self) -> &[Entity] {
+        &self.0
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HierarchyError {
+    EntityNotAlive(Entity),
+    /// The parent is the child itself or one of its descendants
+    Cycle { child: Entity, parent: Entity },
+}
+
+impl Display for HierarchyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HierarchyError::EntityNotAlive(entity) => write!(f, "Entity {:?} is not alive", entity),
+            HierarchyError::Cycle { child, parent } => write!(
+                f,
+                "Entity {:?} cannot be the parent of {:?} because it is one of its descendants",
+                parent, child
+            ),
+        }
+    }
+}
+
+impl std::error::Error for HierarchyError {}
+
+impl World {
+    /// Makes the entity a child of the parent, removing it from its previous parent
+    pub fn set_parent(&mut self, child: Entity, parent: Entity) -> Result<(), HierarchyError> {
+        for entity in [child, parent] {
+            if !self.is_alive(entity) {
+                return Err(HierarchyError::EntityNotAlive(entity));
+            }
+        }
+        if child == parent || self.is_descendant_of(parent, child) {
+            return Err(HierarchyError::Cycle { child, parent });
+        }
+
+        self.remove_parent(child);
+        self.insert(child, Parent(parent));
+        match self.get_mut::<Children>(parent) {
+            Some(children) => children.0.push(child),
+            None => {
+                self.insert(parent, Children(vec![child]));
+            }
+        }
+        Ok(())
+    }
+
+    /// Turns the entity into a root, returns the parent it had
+    pub fn remove_parent(&mut self, child: Entity) -> Option<Entity> {
+        let Parent(parent) = self.remove::<Parent>(child)?;
+        self.remove_child(parent, child);
+        Some(parent)
+    }
+
+    /// Removes the entity from the children of its parent without removing its `Parent`, used when despawning it
+    pub(super) fn detach_from_parent(&mut self, child: Entity) {
+        if let Some(parent) = self.parent(child) {
+            self.remove_child(parent, child);
+        }
+    }
+
+    fn remove_child(&mut self, parent: Entity, child: Entity) {
+        if let Some(children) = self.get_mut::<Children>(parent) {
+            children.0.retain(|entity| *entity != child);
+            if children.0.is_empty() {
+                self.remove::<Children>(parent);
+            }
+        }
+    }
+
+    pub fn parent(&self, entity: Entity) -> Option<Entity> {
+        self.get::<Parent>(entity).map(Parent::entity)
+    }
+
+    pub fn children(&self, entity: Entity) -> &[Entity] {
+        self.get::<Children>(entity)
+            .map_or(&[], Children::entities)
+    }
+
+    pub fn is_descendant_of(&self, entity: Entity, ancestor: Entity) -> bool {
+        let mut current = self.parent(entity);
+        while let Some(parent) = current {
+            if parent == ancestor {
+                return true;
+            }
+            current = self.parent(parent);
+        }
+        false
+    }
+
+    /// Despawns the entity and all its descendants, returns `false` if the entity was not alive
+    pub fn despawn_recursive(&mut self, entity: Entity) -> bool {
+        if !self.is_alive(entity) {
+            return false;
+        }
+        self.remove_parent(entity);
+        let mut stack = vec![entity];
+        while let Some(entity) = stack.pop() {
+            if let Some(children) = self.remove::<Children>(entity) {
+                stack.extend(children.0);
+            }
+            self.despawn(entity);
+        }
+        true
+    }
+
+    /// Updates the `GlobalTransform2D` of every entity with a `Transform2D` and of all their descendants.\
+    /// Entities without a `Transform2D` use the identity, so their descendants get the global transform of their parent
+    pub fn propagate_transforms(&mut self) {
+        let mut roots: Vec<Entity> = self
+            .query::<(Entity, &Transform2D)>()
+            .map(|(entity, _)| entity)
+            .collect();
+        let parents: Vec<Entity> = self
+            .query::<(Entity, &Children)>()
+            .map(|(entity, _)| entity)
+            .collect();
+        roots.extend(parents.into_iter().filter(|entity| !self.has::<Transform2D>(*entity)));
+        let mut stack: Vec<(Entity, Transform2D)> = roots
+            .into_iter()
+            .filter(|entity| self.parent(*entity).map_or(true, |parent| !self.is_alive(parent)))
+            .map(|entity| (entity, Transform2D::IDENTITY))
+            .collect();
+
+        while let Some((entity, parent_global)) = stack.pop() {
+            let global = match self.get::<Transform2D>(entity) {
+                Some(local) => parent_global.mul_transform(local),
+                None => parent_global,
+            };
+            self.insert(entity, GlobalTransform2D(global));
+            stack.extend(self.children(entity).iter().map(|child| (*child, global)));
+        }
+    }
+}
+
+/// Exclusive system that runs `World::propagate_transforms`, usually added at the end of the update stage
+pub fn transform_propagation_system() -> System {
+    System::new("transform_propagation", |world, _| world.propagate_transforms())
+}
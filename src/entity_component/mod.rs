@@ -0,0 +1,21 @@
+pub mod commands;
+pub mod component;
+pub mod hierarchy;
+pub mod public_data;
+pub mod query;
+pub mod resource;
+pub mod schedule;
+pub mod system;
+pub mod world;
+
+pub use commands::{Commands, EntityCommands};
+pub use component::{Component, ComponentStorage, ComponentTicks};
+pub use hierarchy::{Children, GlobalTransform2D, HierarchyError, Parent, Transform2D};
+pub use query::{Added, Changed, ComponentAccess, QueryIter, QueryParam};
+pub use resource::{Resource, Resources};
+pub use schedule::{ExecutionMode, Schedule, ScheduleError, ScheduleRuntime, Stage, SystemTimer};
+pub use system::{FrameTarget, System, SystemContext, WorldView};
+pub use world::{ComponentHook, Entity, World};
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,215 @@
+use std::{
+    any::{Any, TypeId},
+    collections::{HashMap, HashSet, VecDeque},
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+};
+
+use crate::slotmap::{GrowthPolicy, Key, SlotKey, Slotmap};
+
+/// Key to a public data entry of type `T`, it can only be used to get data of that type
+pub struct PublicDataKey<T> {
+    slot_key: SlotKey,
+    data_type: PhantomData<fn() -> T>,
+}
+
+impl<T> Clone for PublicDataKey<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+impl<T> Copy for PublicDataKey<T> {}
+
+impl<T> PartialEq for PublicDataKey<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.slot_key == other.slot_key
+    }
+}
+impl<T> Eq for PublicDataKey<T> {}
+
+impl<T> Hash for PublicDataKey<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.slot_key.hash(state);
+    }
+}
+
+impl<T> std::fmt::Debug for PublicDataKey<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("PublicDataKey").field(&self.slot_key).finish()
+    }
+}
+
+impl<T> Key for PublicDataKey<T> {
+    fn from_slot_key(slot_key: SlotKey) -> Self {
+        Self {
+            slot_key,
+            data_type: PhantomData,
+        }
+    }
+
+    fn slot_key(&self) -> SlotKey {
+        self.slot_key
+    }
+}
+
+impl<T: Send + Sync + 'static> PublicDataKey<T> {
+    pub fn id(&self) -> PublicDataId {
+        PublicDataId {
+            type_id: TypeId::of::<T>(),
+            slot_key: self.slot_key,
+        }
+    }
+}
+
+/// Untyped key to a public data entry, used by the events because they can point to entries of any type
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PublicDataId {
+    type_id: TypeId,
+    slot_key: SlotKey,
+}
+
+impl PublicDataId {
+    pub fn is<T: Send + Sync + 'static>(&self) -> bool {
+        self.type_id == TypeId::of::<T>()
+    }
+
+    /// Returns the typed key if the entry has data of type `T`
+    pub fn downcast<T: Send + Sync + 'static>(&self) -> Option<PublicDataKey<T>> {
+        if self.is::<T>() {
+            Some(PublicDataKey::from_slot_key(self.slot_key))
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PublicDataEvent {
+    Created(PublicDataId),
+    Destroyed(PublicDataId),
+    /// The data was accessed mutably, only one event is queued per entry until the events are cleared
+    Changed(PublicDataId),
+}
+
+impl PublicDataEvent {
+    pub fn id(&self) -> PublicDataId {
+        match *self {
+            PublicDataEvent::Created(id)
+            | PublicDataEvent::Destroyed(id)
+            | PublicDataEvent::Changed(id) => id,
+        }
+    }
+}
+
+/// The Public Data Slotmap (PDS) is sent as a mutable reference on every update, it is how GUI windows and other
+/// entities communicate, because they cannot update each other directly.\
+/// Each data type is stored in its own `Slotmap`, and every creation, destruction and mutable access is added to
+/// an event queue so the entities holding a key can update themselves. The events have to be cleared once per frame
+pub struct PublicDataSlotmap {
+    storages: HashMap<TypeId, Box<dyn Any + Send + Sync>>,
+    event_queue: VecDeque<PublicDataEvent>,
+    /// Entries with a `Changed` event in the queue, so a mutable access does not have to search the queue
+    changed: HashSet<PublicDataId>,
+}
+
+impl Default for PublicDataSlotmap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PublicDataSlotmap {
+    pub fn new() -> Self {
+        Self {
+            storages: HashMap::new(),
+            event_queue: VecDeque::new(),
+            changed: HashSet::new(),
+        }
+    }
+
+    fn storage<T: Send + Sync + 'static>(&self) -> Option<&Slotmap<T, PublicDataKey<T>>> {
+        self.storages
+            .get(&TypeId::of::<T>())
+            .and_then(|storage| storage.downcast_ref())
+    }
+
+    fn storage_mut<T: Send + Sync + 'static>(&mut self) -> Option<&mut Slotmap<T, PublicDataKey<T>>> {
+        self.storages
+            .get_mut(&TypeId::of::<T>())
+            .and_then(|storage| storage.downcast_mut())
+    }
+
+    /// Stores the data and adds a `Created` event with its key to the event queue
+    pub fn create<T: Send + Sync + 'static>(&mut self, data: T) -> Option<PublicDataKey<T>> {
+        let key = self
+            .storages
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| {
+                Box::new(Slotmap::<T, PublicDataKey<T>>::with_growth_policy(
+                    4,
+                    GrowthPolicy::Double,
+                ))
+            })
+            .downcast_mut::<Slotmap<T, PublicDataKey<T>>>()?
+            .push(data)?;
+        self.event_queue.push_back(PublicDataEvent::Created(key.id()));
+        Some(key)
+    }
+
+    /// Removes the data and adds a `Destroyed` event with its key to the event queue
+    pub fn destroy<T: Send + Sync + 'static>(&mut self, key: PublicDataKey<T>) -> Option<T> {
+        let data = self.storage_mut::<T>()?.remove(key)?;
+        self.event_queue.push_back(PublicDataEvent::Destroyed(key.id()));
+        Some(data)
+    }
+
+    pub fn contains<T: Send + Sync + 'static>(&self, key: &PublicDataKey<T>) -> bool {
+        self.storage::<T>()
+            .is_some_and(|storage| storage.is_valid(key))
+    }
+
+    pub fn get<T: Send + Sync + 'static>(&self, key: &PublicDataKey<T>) -> Option<&T> {
+        self.storage::<T>()?.get_value(key)
+    }
+
+    /// Getting the data mutably adds a `Changed` event, even if the data is not modified
+    pub fn get_mut<T: Send + Sync + 'static>(&mut self, key: &PublicDataKey<T>) -> Option<&mut T> {
+        let id = key.id();
+        let storage = self
+            .storages
+            .get_mut(&TypeId::of::<T>())?
+            .downcast_mut::<Slotmap<T, PublicDataKey<T>>>()?;
+        let data = storage.get_value_mut(key)?;
+
+        if self.changed.insert(id) {
+            self.event_queue.push_back(PublicDataEvent::Changed(id));
+        }
+        Some(data)
+    }
+
+    /// Returns `true` if the data was accessed mutably since the events were cleared
+    pub fn was_changed<T: Send + Sync + 'static>(&self, key: &PublicDataKey<T>) -> bool {
+        self.changed.contains(&key.id())
+    }
+
+    pub fn iter<T: Send + Sync + 'static>(&self) -> impl Iterator<Item = (PublicDataKey<T>, &T)> + '_ {
+        self.storage::<T>()
+            .into_iter()
+            .flat_map(|storage| storage.iter())
+    }
+
+    pub fn event_queue(&self) -> &VecDeque<PublicDataEvent> {
+        &self.event_queue
+    }
+
+    /// Should be called after all the entities handled the events of the frame
+    pub fn clear_events(&mut self) {
+        self.event_queue.clear();
+        self.changed.clear();
+    }
+
+    pub fn drain_events(&mut self) -> std::collections::vec_deque::Drain<'_, PublicDataEvent> {
+        self.changed.clear();
+        self.event_queue.drain(..)
+    }
+}
@@ -0,0 +1,278 @@
+use std::{
+    any::{type_name, TypeId},
+    borrow::Cow,
+    marker::PhantomData,
+};
+
+use super::{component::ComponentStorage, Component, Entity, World};
+
+/// Component type accessed by a query or a system
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ComponentAccess {
+    Read(TypeId),
+    Write(TypeId),
+}
+
+impl ComponentAccess {
+    pub fn read<T: Component>() -> Self {
+        ComponentAccess::Read(TypeId::of::<T>())
+    }
+
+    pub fn write<T: Component>() -> Self {
+        ComponentAccess::Write(TypeId::of::<T>())
+    }
+
+    pub fn type_id(&self) -> TypeId {
+        match *self {
+            ComponentAccess::Read(type_id) | ComponentAccess::Write(type_id) => type_id,
+        }
+    }
+
+    /// Two accesses conflict if they are for the same component type and at least one of them writes
+    pub fn conflicts_with(&self, other: &ComponentAccess) -> bool {
+        self.type_id() == other.type_id()
+            && (matches!(self, ComponentAccess::Write(_)) || matches!(other, ComponentAccess::Write(_)))
+    }
+}
+
+/// Returns `true` if any access of the first list conflicts with any access of the second list
+pub fn accesses_conflict(a: &[ComponentAccess], b: &[ComponentAccess]) -> bool {
+    a.iter().any(|a| b.iter().any(|b| a.conflicts_with(b)))
+}
+
+/// Something that can be fetched for each entity by `World::query`, implemented for `&T`, `&mut T`, `Entity`,
+/// the `Added` and `Changed` filters and tuples of them, e.g. `query::<(Entity, &mut Transform, &Velocity)>()`
+///
+/// # Safety
+/// `access` has to report every component the parameter reads or writes, because it is what prevents
+/// `get` from creating aliasing mutable references
+pub unsafe trait QueryParam {
+    type Item<'w>;
+    type Fetch: Copy;
+
+    fn access(access: &mut Vec<ComponentAccess>);
+
+    /// Returns `None` if a component type was never inserted, in which case the query is empty.\
+    /// `last_run_tick` is the tick the system running the query last ran at, used by the change filters
+    fn fetch(world: &World, last_run_tick: u32) -> Option<Self::Fetch>;
+
+    /// The entities the query has to go through, the parameter with the least components is used
+    ///
+    /// # Safety
+    /// The storages cannot be modified while the slice is used
+    unsafe fn entities<'w>(fetch: Self::Fetch) -> Option<&'w [Entity]>;
+
+    /// Does not mark anything as changed, since a later parameter of a tuple can still reject the entity
+    ///
+    /// # Safety
+    /// The accesses of the parameter cannot conflict with other references that are alive for `'w`
+    unsafe fn get<'w>(fetch: Self::Fetch, entity: Entity) -> Option<Self::Item<'w>>;
+
+    /// Called once the item returned by `get` is yielded, marks the components accessed mutably as changed
+    ///
+    /// # Safety
+    /// Same as `get`
+    unsafe fn mark_changed(_fetch: Self::Fetch, _entity: Entity) {}
+}
+
+unsafe impl<T: Component> QueryParam for &T {
+    type Item<'w> = &'w T;
+    type Fetch = *const ComponentStorage<T>;
+
+    fn access(access: &mut Vec<ComponentAccess>) {
+        access.push(ComponentAccess::read::<T>());
+    }
+
+    fn fetch(world: &World, _last_run_tick: u32) -> Option<Self::Fetch> {
+        world.storage_ptr::<T>().map(|storage| storage as *const _)
+    }
+
+    unsafe fn entities<'w>(fetch: Self::Fetch) -> Option<&'w [Entity]> {
+        Some(ComponentStorage::entities_slice(fetch))
+    }
+
+    unsafe fn get<'w>(fetch: Self::Fetch, entity: Entity) -> Option<Self::Item<'w>> {
+        ComponentStorage::component_ptr(fetch, entity).map(|component| &*component)
+    }
+}
+
+unsafe impl<T: Component> QueryParam for &mut T {
+    type Item<'w> = &'w mut T;
+    type Fetch = *mut ComponentStorage<T>;
+
+    fn access(access: &mut Vec<ComponentAccess>) {
+        access.push(ComponentAccess::write::<T>());
+    }
+
+    fn fetch(world: &World, _last_run_tick: u32) -> Option<Self::Fetch> {
+        world.storage_ptr::<T>()
+    }
+
+    unsafe fn entities<'w>(fetch: Self::Fetch) -> Option<&'w [Entity]> {
+        Some(ComponentStorage::entities_slice(fetch))
+    }
+
+    unsafe fn get<'w>(fetch: Self::Fetch, entity: Entity) -> Option<Self::Item<'w>> {
+        ComponentStorage::component_ptr_mut(fetch, entity).map(|component| &mut *component)
+    }
+
+    unsafe fn mark_changed(fetch: Self::Fetch, entity: Entity) {
+        ComponentStorage::mark_changed_ptr(fetch, entity);
+    }
+}
+
+unsafe impl QueryParam for Entity {
+    type Item<'w> = Entity;
+    type Fetch = ();
+
+    fn access(_access: &mut Vec<ComponentAccess>) {}
+
+    fn fetch(_world: &World, _last_run_tick: u32) -> Option<Self::Fetch> {
+        Some(())
+    }
+
+    unsafe fn entities<'w>(_fetch: Self::Fetch) -> Option<&'w [Entity]> {
+        None
+    }
+
+    unsafe fn get<'w>(_fetch: Self::Fetch, entity: Entity) -> Option<Self::Item<'w>> {
+        Some(entity)
+    }
+}
+
+/// Query filter that only matches the entities whose component `T` was inserted since the system last ran, e.g.
+/// `query::<(Entity, &Transform, Added<Transform>)>()`. Outside of the systems it matches the components inserted
+/// since the trackers were cleared.\
+/// It reads `T`, so it cannot be used in a query that writes `T`
+pub struct Added<T>(PhantomData<T>);
+
+/// Query filter that only matches the entities whose component `T` was inserted or accessed mutably since the system
+/// last ran.\
+/// It reads `T`, so it cannot be used in a query that writes `T`
+pub struct Changed<T>(PhantomData<T>);
+
+macro_rules! impl_query_filter {
+    ($filter:ident, $is_newer:ident) => {
+        unsafe impl<T: Component> QueryParam for $filter<T> {
+            type Item<'w> = ();
+            type Fetch = (*const ComponentStorage<T>, u32);
+
+            fn access(access: &mut Vec<ComponentAccess>) {
+                access.push(ComponentAccess::read::<T>());
+            }
+
+            fn fetch(world: &World, last_run_tick: u32) -> Option<Self::Fetch> {
+                world
+                    .storage_ptr::<T>()
+                    .map(|storage| (storage as *const _, last_run_tick))
+            }
+
+            unsafe fn entities<'w>(fetch: Self::Fetch) -> Option<&'w [Entity]> {
+                Some(ComponentStorage::entities_slice(fetch.0))
+            }
+
+            unsafe fn get<'w>(fetch: Self::Fetch, entity: Entity) -> Option<Self::Item<'w>> {
+                let (storage, last_run_tick) = fetch;
+                let (ticks, tick) = ComponentStorage::ticks_ptr(storage, entity)?;
+                ticks.$is_newer(last_run_tick, tick).then_some(())
+            }
+        }
+    };
+}
+
+impl_query_filter!(Added, is_added);
+impl_query_filter!(Changed, is_changed);
+
+macro_rules! impl_query_param_tuple {
+    ($($param:ident),*) => {
+        #[allow(non_snake_case)]
+        unsafe impl<$($param: QueryParam),*> QueryParam for ($($param,)*) {
+            type Item<'w> = ($($param::Item<'w>,)*);
+            type Fetch = ($($param::Fetch,)*);
+
+            fn access(access: &mut Vec<ComponentAccess>) {
+                $($param::access(access);)*
+            }
+
+            fn fetch(world: &World, last_run_tick: u32) -> Option<Self::Fetch> {
+                Some(($($param::fetch(world, last_run_tick)?,)*))
+            }
+
+            unsafe fn entities<'w>(fetch: Self::Fetch) -> Option<&'w [Entity]> {
+                let ($($param,)*) = fetch;
+                let mut smallest: Option<&'w [Entity]> = None;
+                $(
+                    if let Some(entities) = $param::entities($param) {
+                        if smallest.map_or(true, |smallest| entities.len() < smallest.len()) {
+                            smallest = Some(entities);
+                        }
+                    }
+                )*
+                smallest
+            }
+
+            unsafe fn get<'w>(fetch: Self::Fetch, entity: Entity) -> Option<Self::Item<'w>> {
+                let ($($param,)*) = fetch;
+                Some(($($param::get($param, entity)?,)*))
+            }
+
+            unsafe fn mark_changed(fetch: Self::Fetch, entity: Entity) {
+                let ($($param,)*) = fetch;
+                $($param::mark_changed($param, entity);)*
+            }
+        }
+    };
+}
+
+impl_query_param_tuple!(A);
+impl_query_param_tuple!(A, B);
+impl_query_param_tuple!(A, B, C);
+impl_query_param_tuple!(A, B, C, D);
+impl_query_param_tuple!(A, B, C, D, E);
+impl_query_param_tuple!(A, B, C, D, E, F);
+impl_query_param_tuple!(A, B, C, D, E, F, G);
+impl_query_param_tuple!(A, B, C, D, E, F, G, H);
+
+/// Panics if the parameter accesses a component mutably together with any other access to the same component
+pub(super) fn check_query_access<Q: QueryParam>() {
+    let mut access = Vec::new();
+    Q::access(&mut access);
+    for (index, a) in access.iter().enumerate() {
+        if access[index + 1..].iter().any(|b| a.conflicts_with(b)) {
+            panic!(
+                "Query {} accesses a component mutably more than once",
+                type_name::<Q>()
+            );
+        }
+    }
+}
+
+/// Iterates over the entities that have all the components of the query
+pub struct QueryIter<'w, Q: QueryParam> {
+    pub(super) entities: Cow<'w, [Entity]>,
+    pub(super) next_index: usize,
+    pub(super) fetch: Option<Q::Fetch>,
+    pub(super) world: PhantomData<&'w World>,
+}
+
+impl<'w, Q: QueryParam> Iterator for QueryIter<'w, Q> {
+    type Item = Q::Item<'w>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let fetch = self.fetch?;
+        while let Some(entity) = self.entities.get(self.next_index) {
+            self.next_index += 1;
+            //Each entity is only visited once, so the mutable references returned never alias
+            if let Some(item) = unsafe { Q::get(fetch, *entity) } {
+                //Only the entities that match every parameter are marked as changed
+                unsafe { Q::mark_changed(fetch, *entity) };
+                return Some(item);
+            }
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.entities.len() - self.next_index))
+    }
+}
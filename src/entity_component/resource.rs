@@ -0,0 +1,65 @@
+use std::{
+    any::{Any, TypeId},
+    cell::UnsafeCell,
+    collections::HashMap,
+};
+
+/// Any type that can be shared between threads can be used as a resource
+pub trait Resource: Send + Sync + 'static {}
+impl<T: Send + Sync + 'static> Resource for T {}
+
+/// Stores a single value of each type, for the data that is shared by the whole world instead of belonging to an
+/// entity, e.g. the `FontCollection`, the `GUIRects` or the input state
+#[derive(Default)]
+pub struct Resources {
+    resources: HashMap<TypeId, Box<UnsafeCell<dyn Any + Send + Sync>>>,
+}
+
+impl Resources {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.resources.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.resources.is_empty()
+    }
+
+    /// Returns the resource of the same type that was stored before
+    pub fn insert<T: Resource>(&mut self, resource: T) -> Option<T> {
+        let previous = self.remove::<T>();
+        self.resources
+            .insert(TypeId::of::<T>(), Box::new(UnsafeCell::new(resource)));
+        previous
+    }
+
+    pub fn remove<T: Resource>(&mut self) -> Option<T> {
+        let resource = self.resources.remove(&TypeId::of::<T>())?;
+        //The key is the TypeId of the value, so the box always contains a T
+        let resource = unsafe { Box::from_raw(Box::into_raw(resource).cast::<UnsafeCell<T>>()) };
+        Some(resource.into_inner())
+    }
+
+    pub fn contains<T: Resource>(&self) -> bool {
+        self.resources.contains_key(&TypeId::of::<T>())
+    }
+
+    pub fn get<T: Resource>(&self) -> Option<&T> {
+        //No mutable references to the resources can exist while they are borrowed
+        self.get_ptr::<T>().map(|resource| unsafe { &*resource })
+    }
+
+    pub fn get_mut<T: Resource>(&mut self) -> Option<&mut T> {
+        self.get_ptr::<T>().map(|resource| unsafe { &mut *resource })
+    }
+
+    /// The resources are stored by their `TypeId`, so the pointer can be cast without checking
+    pub(super) fn get_ptr<T: Resource>(&self) -> Option<*mut T> {
+        self.resources
+            .get(&TypeId::of::<T>())
+            .map(|resource| resource.get().cast::<T>())
+    }
+}
@@ -0,0 +1,599 @@
+use std::{collections::VecDeque, fmt};
+
+use rayon::prelude::*;
+use winit::window::WindowId;
+
+use crate::{engine::time::Microsecond, Engine, EngineEvent, Runtime};
+
+use super::{
+    commands::Commands,
+    system::{EngineAccess, FrameTarget, ParallelRun, System, SystemContext},
+    World,
+};
+
+/// The phases of the engine loop, in the order they run on each frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Stage {
+    /// Handles the winit and device events of the frame
+    Events,
+    /// Runs zero or more times per frame with the fixed time step of the engine timer
+    FixedUpdate,
+    Update,
+    /// Records the draw commands, the command buffer is submitted after the stage
+    Render,
+    /// Runs after the frame was submitted and presented
+    FrameEnd,
+}
+
+impl Stage {
+    pub const ALL: [Stage; 5] = [
+        Stage::Events,
+        Stage::FixedUpdate,
+        Stage::Update,
+        Stage::Render,
+        Stage::FrameEnd,
+    ];
+
+    fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScheduleError {
+    /// Two systems in the same stage have the same name
+    DuplicateSystem { stage: Stage, system: &'static str },
+    /// An ordering constraint names a system that is not in the same stage
+    UnknownSystem { stage: Stage, system: &'static str, constraint: &'static str },
+    /// The ordering constraints of these systems form a cycle
+    Cycle { stage: Stage, systems: Vec<&'static str> },
+}
+
+impl fmt::Display for ScheduleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScheduleError::DuplicateSystem { stage, system } => {
+                write!(f, "System {} is added more than once to the {:?} stage", system, stage)
+            }
+            ScheduleError::UnknownSystem {
+                stage,
+                system,
+                constraint,
+            } => write!(
+                f,
+                "System {} is ordered with {}, which is not in the {:?} stage",
+                system, constraint, stage
+            ),
+            ScheduleError::Cycle { stage, systems } => write!(
+                f,
+                "The ordering of the systems {:?} in the {:?} stage is cyclic",
+                systems, stage
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ScheduleError {}
+
+/// Defines how the systems of a stage are run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExecutionMode {
+    /// Parallel systems that do not conflict run at the same time on the rayon thread pool
+    #[default]
+    Parallel,
+    /// All the systems run one after the other on the calling thread, in the same order every frame.
+    /// The accesses of the parallel systems are still checked, so it can be used to debug them
+    SingleThreaded,
+}
+
+#[derive(Clone, Copy)]
+pub struct SystemTime {
+    pub name: &'static str,
+    pub stage: Stage,
+    pub time: Microsecond,
+}
+
+/// Time taken by each stage and each system the last time they ran
+#[derive(Default, Clone)]
+pub struct SystemTimer {
+    /// Time taken by the stages, the parallel systems overlap so the time of the systems can add up to more.\
+    /// The fixed update stage and its systems add up the time of all the steps of the frame
+    pub stage_times: [Microsecond; 5],
+    pub system_times: Vec<SystemTime>,
+    /// Number of times the fixed update stage ran this frame
+    pub fixed_steps: u32,
+}
+
+impl SystemTimer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn copy_from(&mut self, other: &Self) {
+        self.stage_times = other.stage_times;
+        self.system_times.clone_from(&other.system_times);
+        self.fixed_steps = other.fixed_steps;
+    }
+
+    pub fn get_stage_time(&self, stage: Stage) -> Microsecond {
+        self.stage_times[stage.index()]
+    }
+
+    pub fn get_system_time(&self, name: &str) -> Option<Microsecond> {
+        self.system_times
+            .iter()
+            .find(|system_time| system_time.name == name)
+            .map(|system_time| system_time.time)
+    }
+
+    pub fn get_total_time(&self) -> Microsecond {
+        self.stage_times
+            .iter()
+            .fold(Microsecond(0), |total, time| total + *time)
+    }
+
+    /// Forgets the fixed steps of the previous frame
+    fn start_frame(&mut self) {
+        self.stage_times[Stage::FixedUpdate.index()] = Microsecond(0);
+        self.system_times
+            .retain(|system_time| system_time.stage != Stage::FixedUpdate);
+        self.fixed_steps = 0;
+    }
+
+    fn record_stage(&mut self, stage: Stage, stage_time: Microsecond, stage_systems: &StageSystems) {
+        if stage == Stage::FixedUpdate {
+            self.record_fixed_step(stage_time, stage_systems);
+            return;
+        }
+        self.stage_times[stage.index()] = stage_time;
+        self.system_times.retain(|system_time| system_time.stage != stage);
+        self.system_times
+            .extend(stage_systems.systems.iter().map(|system| SystemTime {
+                name: system.name,
+                stage,
+                time: system.run_time,
+            }));
+    }
+
+    /// Adds the time of the step to the time of the previous steps of the frame
+    fn record_fixed_step(&mut self, stage_time: Microsecond, stage_systems: &StageSystems) {
+        let stage = Stage::FixedUpdate;
+        self.stage_times[stage.index()] += stage_time;
+        for system in stage_systems.systems.iter() {
+            match self
+                .system_times
+                .iter_mut()
+                .find(|system_time| system_time.stage == stage && system_time.name == system.name)
+            {
+                Some(system_time) => system_time.time += system.run_time,
+                None => self.system_times.push(SystemTime {
+                    name: system.name,
+                    stage,
+                    time: system.run_time,
+                }),
+            }
+        }
+        self.fixed_steps += 1;
+    }
+}
+
+/// Systems that run one after the other, the parallel systems of a batch do not conflict or depend on each other
+enum Batch {
+    Exclusive(usize),
+    Parallel(Vec<usize>),
+}
+
+#[derive(Default)]
+struct StageSystems {
+    systems: Vec<System>,
+    /// Indices of the systems in the order they run, `None` if a system was added after sorting
+    order: Option<Vec<usize>>,
+    batches: Vec<Batch>,
+}
+
+impl StageSystems {
+    /// Sorts the systems following their constraints, systems without constraints between them keep the order they were added
+    fn sort(&mut self, stage: Stage) -> Result<(), ScheduleError> {
+        if self.order.is_some() {
+            return Ok(());
+        }
+
+        let find = |name: &'static str, system: &'static str| {
+            self.systems
+                .iter()
+                .position(|other| other.name == name)
+                .ok_or(ScheduleError::UnknownSystem {
+                    stage,
+                    system,
+                    constraint: name,
+                })
+        };
+
+        //dependencies[i] contains the systems that have to run before system i
+        let mut dependencies = vec![Vec::<usize>::new(); self.systems.len()];
+        for (index, system) in self.systems.iter().enumerate() {
+            if self.systems[..index].iter().any(|other| other.name == system.name) {
+                return Err(ScheduleError::DuplicateSystem {
+                    stage,
+                    system: system.name,
+                });
+            }
+            for after in system.after.iter() {
+                dependencies[index].push(find(after, system.name)?);
+            }
+            for before in system.before.iter() {
+                dependencies[find(before, system.name)?].push(index);
+            }
+        }
+
+        let mut order = Vec::with_capacity(self.systems.len());
+        let mut sorted = vec![false; self.systems.len()];
+        while order.len() < self.systems.len() {
+            let next = (0..self.systems.len()).find(|index| {
+                !sorted[*index] && dependencies[*index].iter().all(|dependency| sorted[*dependency])
+            });
+            match next {
+                Some(index) => {
+                    sorted[index] = true;
+                    order.push(index);
+                }
+                None => {
+                    return Err(ScheduleError::Cycle {
+                        stage,
+                        systems: (0..self.systems.len())
+                            .filter(|index| !sorted[*index])
+                            .map(|index| self.systems[index].name)
+                            .collect(),
+                    })
+                }
+            }
+        }
+
+        self.batches = Self::create_batches(&self.systems, &order, &dependencies);
+        self.order = Some(order);
+        Ok(())
+    }
+
+    /// Groups consecutive parallel systems while they do not conflict or depend on a system of the batch
+    fn create_batches(systems: &[System], order: &[usize], dependencies: &[Vec<usize>]) -> Vec<Batch> {
+        let mut batches = Vec::new();
+        let mut current = Vec::<usize>::new();
+        for index in order.iter().copied() {
+            let system = &systems[index];
+            if !system.is_parallel() {
+                if !current.is_empty() {
+                    batches.push(Batch::Parallel(std::mem::take(&mut current)));
+                }
+                batches.push(Batch::Exclusive(index));
+                continue;
+            }
+
+            let blocked = current.iter().any(|other| {
+                systems[*other].conflicts_with(system) || dependencies[index].contains(other)
+            });
+            if blocked {
+                batches.push(Batch::Parallel(std::mem::take(&mut current)));
+            }
+            current.push(index);
+        }
+        if !current.is_empty() {
+            batches.push(Batch::Parallel(current));
+        }
+        batches
+    }
+
+    /// The systems that never ran see the changes since the trackers were cleared, and the world is left with the
+    /// observer it had before the stage
+    fn run(&mut self, world: &mut World, context: &mut SystemContext, execution_mode: ExecutionMode) {
+        let first_run_tick = world.last_change_tick();
+        self.run_systems(world, context, execution_mode, first_run_tick);
+        world.set_last_change_tick(first_run_tick);
+    }
+
+    fn run_systems(
+        &mut self,
+        world: &mut World,
+        context: &mut SystemContext,
+        execution_mode: ExecutionMode,
+        first_run_tick: u32,
+    ) {
+        if execution_mode == ExecutionMode::SingleThreaded {
+            let order = self.order.as_ref().expect("Stage was not sorted");
+            for index in order.iter() {
+                self.systems[*index].run(world, context, first_run_tick);
+                world.increment_change_tick();
+            }
+            return;
+        }
+
+        for batch in self.batches.iter() {
+            match batch {
+                Batch::Exclusive(index) => self.systems[*index].run(world, context, first_run_tick),
+                Batch::Parallel(indices) if indices.len() == 1 => {
+                    self.systems[indices[0]].run(world, context, first_run_tick)
+                }
+                Batch::Parallel(indices) => {
+                    let mut runs: Vec<(usize, ParallelRun)> = self
+                        .systems
+                        .iter_mut()
+                        .enumerate()
+                        .filter_map(|(index, system)| {
+                            let position = indices.iter().position(|batch_index| *batch_index == index)?;
+                            Some((position, ParallelRun::new(system)?))
+                        })
+                        .collect();
+                    runs.sort_unstable_by_key(|(position, _)| *position);
+                    let shared_world: &World = world;
+                    let shared_context: &SystemContext = context;
+                    //The systems of a batch do not have conflicting accesses
+                    let batch_commands: Vec<Commands> = runs
+                        .par_iter_mut()
+                        .map(|(_, run)| unsafe { run.run(shared_world, shared_context, first_run_tick) })
+                        .collect();
+                    //The collected commands keep the order of the batch, which is the order of the schedule
+                    for mut commands in batch_commands {
+                        context.commands.append(&mut commands);
+                    }
+                }
+            }
+            //The systems of a parallel batch do not read what the others write, so they can share a tick
+            world.increment_change_tick();
+        }
+    }
+
+    /// The oldest tick a system of the stage last ran at, relative to the current tick
+    fn oldest_last_run_tick(&self, change_tick: u32) -> Option<u32> {
+        self.systems
+            .iter()
+            .filter_map(|system| system.last_run_tick)
+            .max_by_key(|tick| change_tick.wrapping_sub(*tick))
+    }
+}
+
+/// Stores the systems of each stage and runs them in the order given by their constraints
+#[derive(Default)]
+pub struct Schedule {
+    stages: [StageSystems; 5],
+    execution_mode: ExecutionMode,
+    timer: SystemTimer,
+}
+
+impl Schedule {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn execution_mode(&self) -> ExecutionMode {
+        self.execution_mode
+    }
+
+    pub fn set_execution_mode(&mut self, execution_mode: ExecutionMode) {
+        self.execution_mode = execution_mode;
+    }
+
+    /// Time taken by the stages and systems the last time they ran
+    pub fn timer(&self) -> &SystemTimer {
+        &self.timer
+    }
+
+    /// Starts timing a new frame, the fixed update times add up until the next call.\
+    /// `ScheduleRuntime` calls it at the start of each frame
+    pub fn start_frame(&mut self) {
+        self.timer.start_frame();
+    }
+
+    pub fn add_system(&mut self, stage: Stage, system: System) -> &mut Self {
+        let stage_systems = &mut self.stages[stage.index()];
+        stage_systems.systems.push(system);
+        stage_systems.order = None;
+        self
+    }
+
+    /// Checks the constraints of all the stages and computes the order of their systems.\
+    /// It is called when running a stage, calling it before starting the engine loop reports the errors earlier
+    pub fn initialize(&mut self) -> Result<(), ScheduleError> {
+        for stage in Stage::ALL {
+            self.stages[stage.index()].sort(stage)?;
+        }
+        Ok(())
+    }
+
+    /// The names of the systems of the stage in the order they run
+    pub fn system_order(&mut self, stage: Stage) -> Result<Vec<&'static str>, ScheduleError> {
+        let stage_systems = &mut self.stages[stage.index()];
+        stage_systems.sort(stage)?;
+        let order = stage_systems.order.as_ref().expect("Stage was not sorted");
+        Ok(order
+            .iter()
+            .map(|index| stage_systems.systems[*index].name)
+            .collect())
+    }
+
+    /// The names of the systems of the stage grouped by the batches that run at the same time in parallel mode
+    pub fn system_batches(&mut self, stage: Stage) -> Result<Vec<Vec<&'static str>>, ScheduleError> {
+        let stage_systems = &mut self.stages[stage.index()];
+        stage_systems.sort(stage)?;
+        let name = |index: &usize| stage_systems.systems[*index].name;
+        Ok(stage_systems
+            .batches
+            .iter()
+            .map(|batch| match batch {
+                Batch::Exclusive(index) => vec![name(index)],
+                Batch::Parallel(indices) => indices.iter().map(name).collect(),
+            })
+            .collect())
+    }
+
+    /// The pairs of systems of the stage that cannot run at the same time because of their accesses,
+    /// exclusive systems are not included because they never run at the same time as other systems
+    pub fn conflicting_systems(&self, stage: Stage) -> Vec<(&'static str, &'static str)> {
+        let systems = &self.stages[stage.index()].systems;
+        let mut conflicts = Vec::new();
+        for (index, system) in systems.iter().enumerate() {
+            for other in systems[index + 1..].iter() {
+                if system.is_parallel() && other.is_parallel() && system.conflicts_with(other) {
+                    conflicts.push((system.name, other.name));
+                }
+            }
+        }
+        conflicts
+    }
+
+    /// Same as `World::clear_trackers`, but the removed entities are kept until every system of the schedule has seen
+    /// them, e.g. a fixed update system that did not run this frame.\
+    /// `ScheduleRuntime` calls it at the end of every frame
+    pub fn clear_trackers(&self, world: &mut World) {
+        let change_tick = world.change_tick();
+        let oldest_last_run_tick = self
+            .stages
+            .iter()
+            .filter_map(|stage_systems| stage_systems.oldest_last_run_tick(change_tick))
+            .max_by_key(|tick| change_tick.wrapping_sub(*tick))
+            .unwrap_or(change_tick);
+        world.clear_trackers_seen_by(oldest_last_run_tick);
+    }
+
+    /// Runs the systems of the stage and then applies the commands they added.\
+    /// Panics if the ordering constraints of the stage are not valid
+    pub fn run_stage(&mut self, stage: Stage, world: &mut World, context: &mut SystemContext) {
+        let stage_start = std::time::Instant::now();
+        let stage_systems = &mut self.stages[stage.index()];
+        if let Err(error) = stage_systems.sort(stage) {
+            panic!("{}", error);
+        }
+        stage_systems.run(world, context, self.execution_mode);
+        context.commands.apply(world);
+        self.timer.record_stage(
+            stage,
+            Microsecond(stage_start.elapsed().as_micros()),
+            stage_systems,
+        );
+    }
+}
+
+/// `Runtime` that runs the systems of a schedule on each phase of the engine loop
+pub struct ScheduleRuntime {
+    window_id: WindowId,
+    pub world: World,
+    pub schedule: Schedule,
+    /// The render phase cannot close the engine loop, so exit requests from the render stage wait until the frame end
+    render_exit_requested: bool,
+    engine_resource: bool,
+}
+
+impl ScheduleRuntime {
+    /// Panics if the ordering constraints of the schedule are not valid
+    pub fn new(window_id: WindowId, world: World, mut schedule: Schedule) -> Self {
+        if let Err(error) = schedule.initialize() {
+            panic!("{}", error);
+        }
+        Self {
+            window_id,
+            world,
+            schedule,
+            render_exit_requested: false,
+            engine_resource: false,
+        }
+    }
+
+    /// Exposes the `Engine` as a resource of the world while the systems run.\
+    /// The world only gives shared references to it, so `SystemContext::engine_mut` returns `None` in every stage
+    pub fn with_engine_resource(mut self) -> Self {
+        self.engine_resource = true;
+        self
+    }
+
+    fn engine_access<'a>(&self, engine: &'a mut Engine) -> EngineAccess<'a> {
+        if self.engine_resource {
+            EngineAccess::Shared(engine)
+        } else {
+            EngineAccess::Exclusive(engine)
+        }
+    }
+
+    fn run_stage(&mut self, stage: Stage, context: &mut SystemContext) {
+        match context.engine() {
+            Some(engine) if self.engine_resource => {
+                let schedule = &mut self.schedule;
+                let engine: *const Engine = engine;
+                //The context only has a shared reference to the engine, so nothing can modify it during the stage
+                unsafe {
+                    self.world
+                        .with_engine_resource(engine, |world| schedule.run_stage(stage, world, context));
+                }
+            }
+            _ => self.schedule.run_stage(stage, &mut self.world, context),
+        }
+    }
+}
+
+impl Runtime for ScheduleRuntime {
+    fn get_window_id(&self) -> WindowId {
+        self.window_id
+    }
+
+    fn frame_start(&mut self, _engine: &Engine) {
+        self.schedule.start_frame();
+    }
+
+    fn handle_event_queue<F>(
+        &mut self,
+        event_queue: &VecDeque<EngineEvent>,
+        engine: &mut Engine,
+        exit_event_loop: &mut F,
+    ) where
+        F: FnMut(),
+    {
+        let mut context = SystemContext::new(self.engine_access(engine)).with_event_queue(event_queue);
+        self.run_stage(Stage::Events, &mut context);
+        if context.exit_requested() {
+            exit_event_loop();
+        }
+    }
+
+    fn fixed_update(&mut self, engine: &Engine, exit_event_loop: &mut dyn FnMut()) {
+        let mut context = SystemContext::new(EngineAccess::Shared(engine));
+        self.run_stage(Stage::FixedUpdate, &mut context);
+        if context.exit_requested() {
+            exit_event_loop();
+        }
+    }
+
+    fn update(&mut self, engine: &Engine, exit_event_loop: &mut dyn FnMut()) {
+        let mut context = SystemContext::new(EngineAccess::Shared(engine));
+        self.run_stage(Stage::Update, &mut context);
+        if context.exit_requested() {
+            exit_event_loop();
+        }
+    }
+
+    fn render(
+        &mut self,
+        engine: &Engine,
+        screen_view: &wgpu::TextureView,
+        encoder: &mut wgpu::CommandEncoder,
+    ) {
+        let mut context = SystemContext::new(EngineAccess::Shared(engine)).with_frame_target(
+            FrameTarget {
+                screen_view,
+                encoder,
+            },
+        );
+        self.run_stage(Stage::Render, &mut context);
+        self.render_exit_requested |= context.exit_requested();
+    }
+
+    fn frame_end<F>(&mut self, engine: &mut Engine, exit_event_loop: &mut F)
+    where
+        F: FnMut(),
+    {
+        let mut context = SystemContext::new(self.engine_access(engine));
+        self.run_stage(Stage::FrameEnd, &mut context);
+        self.schedule.clear_trackers(&mut self.world);
+        if context.exit_requested() || self.render_exit_requested {
+            exit_event_loop();
+        }
+    }
+
+    fn before_exit(&mut self, _engine: &Engine) {}
+}
@@ -0,0 +1,356 @@
+use std::{
+    any::type_name,
+    collections::VecDeque,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use crate::{engine::time::Microsecond, Engine, EngineEvent};
+
+use super::{
+    commands::Commands,
+    query::{ComponentAccess, QueryIter, QueryParam},
+    resource::Resource,
+    Component, Entity, World,
+};
+
+pub(super) enum EngineAccess<'a> {
+    Shared(&'a Engine),
+    Exclusive(&'a mut Engine),
+    /// The stages are run without an engine loop, e.g. by tests and tools that do not create the graphics
+    None,
+}
+
+/// The screen texture and the command encoder of the frame, only available in the render stage
+pub struct FrameTarget<'a> {
+    pub screen_view: &'a wgpu::TextureView,
+    pub encoder: &'a mut wgpu::CommandEncoder,
+}
+
+/// Data of the engine phase that is running the systems.\
+/// The engine can only be modified in the events and frame end stages, like in the `Runtime` functions
+pub struct SystemContext<'a> {
+    engine: EngineAccess<'a>,
+    event_queue: Option<&'a VecDeque<EngineEvent>>,
+    frame_target: Option<FrameTarget<'a>>,
+    exit_requested: AtomicBool,
+    pub(super) commands: Commands,
+}
+
+impl<'a> SystemContext<'a> {
+    pub(super) fn new(engine: EngineAccess<'a>) -> Self {
+        Self {
+            engine,
+            event_queue: None,
+            frame_target: None,
+            exit_requested: AtomicBool::new(false),
+            commands: Commands::new(),
+        }
+    }
+
+    /// Context of a stage that runs without an engine, `engine` and `engine_mut` return `None`
+    pub fn without_engine() -> Self {
+        Self::new(EngineAccess::None)
+    }
+
+    pub(super) fn with_event_queue(mut self, event_queue: &'a VecDeque<EngineEvent>) -> Self {
+        self.event_queue = Some(event_queue);
+        self
+    }
+
+    pub(super) fn with_frame_target(mut self, frame_target: FrameTarget<'a>) -> Self {
+        self.frame_target = Some(frame_target);
+        self
+    }
+
+    /// Returns `None` if the stage runs without an engine
+    pub fn engine(&self) -> Option<&Engine> {
+        match &self.engine {
+            EngineAccess::Shared(engine) => Some(engine),
+            EngineAccess::Exclusive(engine) => Some(engine),
+            EngineAccess::None => None,
+        }
+    }
+
+    /// Returns `None` outside of the events and frame end stages
+    pub fn engine_mut(&mut self) -> Option<&mut Engine> {
+        match &mut self.engine {
+            EngineAccess::Exclusive(engine) => Some(engine),
+            EngineAccess::Shared(_) | EngineAccess::None => None,
+        }
+    }
+
+    /// Returns `None` outside of the events stage
+    pub fn event_queue(&self) -> Option<&VecDeque<EngineEvent>> {
+        self.event_queue
+    }
+
+    /// Returns `None` outside of the render stage
+    pub fn frame_target(&mut self) -> Option<&mut FrameTarget<'a>> {
+        self.frame_target.as_mut()
+    }
+
+    /// The commands are applied to the world after all the systems of the stage run
+    pub fn commands(&self) -> &Commands {
+        &self.commands
+    }
+
+    /// Context of a system that runs in a parallel batch, with its own commands so the commands of the batch can be
+    /// merged in the order of the schedule instead of the order the threads add them
+    fn for_parallel_system(&self) -> SystemContext<'_> {
+        let engine = match &self.engine {
+            EngineAccess::Shared(engine) => EngineAccess::Shared(engine),
+            EngineAccess::Exclusive(engine) => EngineAccess::Shared(engine),
+            EngineAccess::None => EngineAccess::None,
+        };
+        SystemContext {
+            engine,
+            event_queue: self.event_queue,
+            frame_target: None,
+            exit_requested: AtomicBool::new(false),
+            commands: Commands::new(),
+        }
+    }
+
+    /// The engine loop is closed after the current stage finishes running its systems
+    pub fn exit(&self) {
+        self.exit_requested.store(true, Ordering::Relaxed);
+    }
+
+    pub fn exit_requested(&self) -> bool {
+        self.exit_requested.load(Ordering::Relaxed)
+    }
+}
+
+/// Access to the components a parallel system declared, entities cannot be spawned or despawned through it
+pub struct WorldView<'w> {
+    world: &'w World,
+    system_name: &'static str,
+    access: &'w [ComponentAccess],
+    /// The change filters of the queries report the changes made since the system last ran
+    last_run_tick: u32,
+}
+
+impl<'w> WorldView<'w> {
+    fn check_access(&self, requested: ComponentAccess, component_name: &str) {
+        let allowed = self.access.iter().any(|declared| match requested {
+            ComponentAccess::Read(_) => declared.type_id() == requested.type_id(),
+            ComponentAccess::Write(_) => *declared == requested,
+        });
+        if !allowed {
+            panic!(
+                "System {} uses {:?} of {} without declaring it",
+                self.system_name, requested, component_name
+            );
+        }
+    }
+
+    /// Same as `World::query`, panics if the system did not declare the accesses of the query
+    pub fn query<Q: QueryParam>(&mut self) -> QueryIter<'_, Q> {
+        let mut query_access = Vec::new();
+        Q::access(&mut query_access);
+        for requested in query_access {
+            self.check_access(requested, type_name::<Q>());
+        }
+        //The view is borrowed mutably so it can only have one query at a time, and the systems that run at the same time
+        //do not have conflicting accesses
+        unsafe { self.world.query_unchecked::<Q>(self.last_run_tick) }
+    }
+
+    pub fn get<T: Component>(&self, entity: Entity) -> Option<&T> {
+        self.check_access(ComponentAccess::read::<T>(), type_name::<T>());
+        self.world.get(entity)
+    }
+
+    pub fn get_mut<T: Component>(&mut self, entity: Entity) -> Option<&mut T> {
+        self.check_access(ComponentAccess::write::<T>(), type_name::<T>());
+        let storage = self.world.storage_ptr::<T>()?;
+        //Only this system can write the component while the view is alive
+        unsafe { (*storage).get_mut(entity) }
+    }
+
+    /// Same as `World::resource`, the system has to declare that it reads the resource
+    pub fn resource<T: Resource>(&self) -> Option<&T> {
+        self.check_access(ComponentAccess::read::<T>(), type_name::<T>());
+        self.world.resource()
+    }
+
+    /// Same as `World::resource_mut`, the system has to declare that it writes the resource
+    pub fn resource_mut<T: Resource>(&mut self) -> Option<&mut T> {
+        self.check_access(ComponentAccess::write::<T>(), type_name::<T>());
+        let resource = self.world.resources().get_ptr::<T>()?;
+        //Only this system can write the resource while the view is alive
+        unsafe { Some(&mut *resource) }
+    }
+
+    pub fn is_alive(&self, entity: Entity) -> bool {
+        self.world.is_alive(entity)
+    }
+
+    pub fn entity_count(&self) -> usize {
+        self.world.entity_count()
+    }
+}
+
+type ExclusiveFunction = Box<dyn FnMut(&mut World, &mut SystemContext)>;
+type ParallelFunction = Box<dyn FnMut(&mut WorldView, &SystemContext) + Send>;
+
+pub(super) enum SystemFunction {
+    Exclusive(ExclusiveFunction),
+    Parallel(ParallelFunction),
+}
+
+/// A named function that runs on the world during one of the stages of the schedule.\
+/// The name is used by the ordering constraints of other systems in the same stage
+pub struct System {
+    pub(super) name: &'static str,
+    pub(super) before: Vec<&'static str>,
+    pub(super) after: Vec<&'static str>,
+    pub(super) access: Vec<ComponentAccess>,
+    pub(super) function: SystemFunction,
+    /// Time the system took the last time it ran
+    pub(super) run_time: Microsecond,
+    /// Change tick of the world when the system last ran, `None` until it runs for the first time
+    pub(super) last_run_tick: Option<u32>,
+}
+
+impl System {
+    /// The system gets exclusive access to the world, so it never runs at the same time as other systems
+    pub fn new<F>(name: &'static str, function: F) -> Self
+    where
+        F: FnMut(&mut World, &mut SystemContext) + 'static,
+    {
+        Self::with_function(name, SystemFunction::Exclusive(Box::new(function)))
+    }
+
+    /// The system can only use the components declared with `reads` and `writes`,
+    /// and it runs at the same time as the other parallel systems that do not conflict with it
+    pub fn parallel<F>(name: &'static str, function: F) -> Self
+    where
+        F: FnMut(&mut WorldView, &SystemContext) + Send + 'static,
+    {
+        Self::with_function(name, SystemFunction::Parallel(Box::new(function)))
+    }
+
+    fn with_function(name: &'static str, function: SystemFunction) -> Self {
+        Self {
+            name,
+            before: Vec::new(),
+            after: Vec::new(),
+            access: Vec::new(),
+            function,
+            run_time: Microsecond(0),
+            last_run_tick: None,
+        }
+    }
+
+    /// The system runs before the system with the given name
+    pub fn before(mut self, name: &'static str) -> Self {
+        self.before.push(name);
+        self
+    }
+
+    /// The system runs after the system with the given name
+    pub fn after(mut self, name: &'static str) -> Self {
+        self.after.push(name);
+        self
+    }
+
+    /// Declares that the system reads the component or resource, only used by parallel systems
+    pub fn reads<T: Component>(mut self) -> Self {
+        self.access.push(ComponentAccess::read::<T>());
+        self
+    }
+
+    /// Declares that the system reads and writes the component or resource, only used by parallel systems
+    pub fn writes<T: Component>(mut self) -> Self {
+        self.access.push(ComponentAccess::write::<T>());
+        self
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn is_parallel(&self) -> bool {
+        matches!(self.function, SystemFunction::Parallel(_))
+    }
+
+    /// Exclusive systems conflict with every other system
+    pub fn conflicts_with(&self, other: &System) -> bool {
+        match (&self.function, &other.function) {
+            (SystemFunction::Parallel(_), SystemFunction::Parallel(_)) => {
+                super::query::accesses_conflict(&self.access, &other.access)
+            }
+            _ => true,
+        }
+    }
+
+    /// The system sees the changes made since it last ran, or since `first_run_tick` the first time it runs.\
+    /// The caller has to increment the change tick of the world afterwards, so the next changes are newer than the run
+    pub(super) fn run(&mut self, world: &mut World, context: &mut SystemContext, first_run_tick: u32) {
+        let start_time = std::time::Instant::now();
+        let last_run_tick = self.last_run_tick.unwrap_or(first_run_tick);
+        world.set_last_change_tick(last_run_tick);
+        match &mut self.function {
+            SystemFunction::Exclusive(function) => function(world, context),
+            SystemFunction::Parallel(function) => {
+                let mut view = WorldView {
+                    world,
+                    system_name: self.name,
+                    access: &self.access,
+                    last_run_tick,
+                };
+                function(&mut view, context);
+            }
+        }
+        self.last_run_tick = Some(world.change_tick());
+        self.run_time = Microsecond(start_time.elapsed().as_micros());
+    }
+}
+
+/// The parts of a parallel system that are sent to the thread that runs it
+pub(super) struct ParallelRun<'s> {
+    name: &'static str,
+    access: &'s [ComponentAccess],
+    function: &'s mut (dyn FnMut(&mut WorldView, &SystemContext) + Send),
+    run_time: &'s mut Microsecond,
+    last_run_tick: &'s mut Option<u32>,
+}
+
+impl<'s> ParallelRun<'s> {
+    pub(super) fn new(system: &'s mut System) -> Option<Self> {
+        match &mut system.function {
+            SystemFunction::Parallel(function) => Some(Self {
+                name: system.name,
+                access: &system.access,
+                function: function.as_mut(),
+                run_time: &mut system.run_time,
+                last_run_tick: &mut system.last_run_tick,
+            }),
+            SystemFunction::Exclusive(_) => None,
+        }
+    }
+
+    /// Same as `System::run`, returns the commands added by the system so the caller can append them to the stage
+    /// commands in schedule order
+    ///
+    /// # Safety
+    /// The accesses of the systems that run at the same time cannot conflict
+    pub(super) unsafe fn run(&mut self, world: &World, context: &SystemContext, first_run_tick: u32) -> Commands {
+        let start_time = std::time::Instant::now();
+        let system_context = context.for_parallel_system();
+        let mut view = WorldView {
+            world,
+            system_name: self.name,
+            access: self.access,
+            last_run_tick: self.last_run_tick.unwrap_or(first_run_tick),
+        };
+        (self.function)(&mut view, &system_context);
+        if system_context.exit_requested() {
+            context.exit();
+        }
+        *self.last_run_tick = Some(world.change_tick());
+        *self.run_time = Microsecond(start_time.elapsed().as_micros());
+        system_context.commands
+    }
+}
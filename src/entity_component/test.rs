@@ -0,0 +1,813 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use glam::Vec2;
+
+    use crate::entity_component::{
+        public_data::{PublicDataEvent, PublicDataKey, PublicDataSlotmap},
+        Added, Changed, Children, Commands, Entity, GlobalTransform2D, HierarchyError, Schedule, ScheduleError, Stage,
+        ExecutionMode, System, SystemContext, Transform2D, World,
+    };
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    struct Transform {
+        x: f32,
+        y: f32,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    struct Velocity {
+        x: f32,
+        y: f32,
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Name(&'static str);
+
+    #[test]
+    fn spawn_and_despawn_entities(){
+        let mut world = World::with_capacity(2);
+        let entities: Vec<Entity> = (0..10).map(|_| world.spawn()).collect();
+        assert_eq!(world.entity_count(), 10);
+
+        world.insert(entities[3], Name("three"));
+        assert!(world.despawn(entities[3]), "The entity should be alive");
+        assert!(!world.despawn(entities[3]), "The entity was already despawned");
+        assert!(!world.is_alive(entities[3]));
+        assert_eq!(world.get::<Name>(entities[3]), None, "Components of despawned entities should be removed");
+        assert_eq!(world.storage::<Name>().map(|storage| storage.len()), Some(0));
+
+        let reused = world.spawn();
+        assert_ne!(reused, entities[3], "The despawned key should not be valid for the new entity");
+        assert!(!world.insert(entities[3], Name("stale")), "Components cannot be added to despawned entities");
+        assert!(!world.has::<Name>(reused));
+    }
+
+    #[test]
+    fn stale_entities_do_not_desync_the_storage(){
+        let mut world = World::with_capacity(1);
+        let dead = world.spawn();
+        world.despawn(dead);
+        //The slot of the dead entity is reused with a newer generation
+        let alive = world.spawn();
+        world.insert(alive, Name("alive"));
+
+        let storage = world.storage_mut::<Name>().expect("The storage should exist");
+        assert_eq!(storage.insert(dead, Name("dead")), None);
+        assert_eq!(storage.len(), 1);
+        assert!(!storage.contains(dead));
+        assert_eq!(storage.remove(alive), Some(Name("alive")));
+        assert!(storage.is_empty());
+    }
+
+    #[test]
+    fn insert_get_and_remove_components(){
+        let mut world = World::new();
+        let entity = world.spawn();
+        let other = world.spawn();
+
+        world.insert(entity, Transform { x: 1.0, y: 2.0 });
+        world.insert(other, Transform { x: 5.0, y: 5.0 });
+        world.insert(entity, Velocity { x: 0.5, y: 0.0 });
+
+        assert!(world.has::<Velocity>(entity));
+        assert!(!world.has::<Velocity>(other));
+        world.get_mut::<Transform>(entity).unwrap().x = 10.0;
+        assert_eq!(world.get::<Transform>(entity), Some(&Transform { x: 10.0, y: 2.0 }));
+
+        world.insert(entity, Transform { x: 0.0, y: 0.0 });
+        assert_eq!(world.storage::<Transform>().unwrap().len(), 2, "Inserting again should replace the component");
+
+        assert_eq!(world.remove::<Transform>(entity), Some(Transform { x: 0.0, y: 0.0 }));
+        assert_eq!(world.remove::<Transform>(entity), None);
+        assert_eq!(world.get::<Transform>(other), Some(&Transform { x: 5.0, y: 5.0 }), "The moved component should still be found");
+        assert_eq!(world.remove::<Name>(entity), None, "Component types that were never inserted are not found");
+    }
+
+    #[test]
+    fn queries_only_visit_entities_with_all_the_components(){
+        let mut world = World::new();
+        let mut moving = Vec::new();
+        for i in 0..10 {
+            let entity = world.spawn();
+            world.insert(entity, Transform { x: i as f32, y: 0.0 });
+            if i % 2 == 0 {
+                world.insert(entity, Velocity { x: 1.0, y: 2.0 });
+                moving.push(entity);
+            }
+        }
+
+        for (transform, velocity) in world.query::<(&mut Transform, &Velocity)>() {
+            transform.x += velocity.x;
+            transform.y += velocity.y;
+        }
+
+        let mut updated: Vec<(Entity, Transform)> = world
+            .query::<(Entity, &Transform, &Velocity)>()
+            .map(|(entity, transform, _)| (entity, *transform))
+            .collect();
+        updated.sort_by(|a, b| a.1.x.partial_cmp(&b.1.x).unwrap());
+        assert_eq!(updated.len(), 5, "Only entities with velocity should be visited");
+        for (i, (entity, transform)) in updated.iter().enumerate() {
+            assert_eq!(*entity, moving[i]);
+            assert_eq!(*transform, Transform { x: (i * 2) as f32 + 1.0, y: 2.0 });
+        }
+
+        let still_count = world
+            .query::<&Transform>()
+            .filter(|transform| transform.y == 0.0)
+            .count();
+        assert_eq!(still_count, 5);
+        assert_eq!(world.query::<Entity>().count(), 10, "Entity queries should visit all the entities");
+        assert_eq!(world.query::<(&Transform, &Name)>().count(), 0, "Missing component types make the query empty");
+    }
+
+    #[test]
+    #[should_panic]
+    fn queries_with_aliasing_mutable_access_panic(){
+        let mut world = World::new();
+        let entity = world.spawn();
+        world.insert(entity, Transform { x: 0.0, y: 0.0 });
+        world.query::<(&mut Transform, &Transform)>().count();
+    }
+
+    fn empty_system(name: &'static str) -> System {
+        System::new(name, |_, _| {})
+    }
+
+    #[test]
+    fn systems_are_ordered_by_their_constraints(){
+        let mut schedule = Schedule::new();
+        schedule
+            .add_system(Stage::Update, empty_system("animation").after("physics"))
+            .add_system(Stage::Update, empty_system("input"))
+            .add_system(Stage::Update, empty_system("physics").after("input"))
+            .add_system(Stage::Update, empty_system("camera").before("input"))
+            .add_system(Stage::Render, empty_system("sprites"));
+
+        assert_eq!(
+            schedule.system_order(Stage::Update),
+            Ok(vec!["camera", "input", "physics", "animation"])
+        );
+        assert_eq!(schedule.system_order(Stage::Render), Ok(vec!["sprites"]));
+        assert_eq!(schedule.system_order(Stage::Events), Ok(vec![]));
+
+        schedule.add_system(Stage::Update, empty_system("audio"));
+        assert_eq!(
+            schedule.system_order(Stage::Update),
+            Ok(vec!["camera", "input", "physics", "animation", "audio"]),
+            "Systems without constraints should keep the order they were added"
+        );
+    }
+
+    #[test]
+    fn invalid_constraints_are_reported(){
+        let mut schedule = Schedule::new();
+        schedule
+            .add_system(Stage::Update, empty_system("a").after("b"))
+            .add_system(Stage::Update, empty_system("b").after("a"))
+            .add_system(Stage::Update, empty_system("c"));
+        assert_eq!(
+            schedule.initialize(),
+            Err(ScheduleError::Cycle { stage: Stage::Update, systems: vec!["a", "b"] })
+        );
+
+        let mut schedule = Schedule::new();
+        schedule
+            .add_system(Stage::Render, empty_system("sprites"))
+            .add_system(Stage::Update, empty_system("physics").before("sprites"));
+        assert_eq!(
+            schedule.initialize(),
+            Err(ScheduleError::UnknownSystem { stage: Stage::Update, system: "physics", constraint: "sprites" }),
+            "Constraints only apply to systems in the same stage"
+        );
+
+        let mut schedule = Schedule::new();
+        schedule
+            .add_system(Stage::Events, empty_system("input"))
+            .add_system(Stage::Events, empty_system("input"));
+        assert_eq!(
+            schedule.initialize(),
+            Err(ScheduleError::DuplicateSystem { stage: Stage::Events, system: "input" })
+        );
+    }
+
+    fn parallel_system(name: &'static str) -> System {
+        System::parallel(name, |_, _| {})
+    }
+
+    #[test]
+    fn parallel_systems_are_batched_by_their_accesses(){
+        let mut schedule = Schedule::new();
+        schedule
+            .add_system(Stage::Update, parallel_system("move").writes::<Transform>().reads::<Velocity>())
+            .add_system(Stage::Update, parallel_system("names").reads::<Name>())
+            .add_system(Stage::Update, parallel_system("accelerate").writes::<Velocity>())
+            .add_system(Stage::Update, parallel_system("draw_names").reads::<Name>().reads::<Transform>())
+            .add_system(Stage::Update, empty_system("spawner"))
+            .add_system(Stage::Update, parallel_system("rename").writes::<Name>())
+            .add_system(Stage::Update, parallel_system("late").reads::<Velocity>().after("rename"));
+
+        assert_eq!(
+            schedule.system_batches(Stage::Update),
+            Ok(vec![
+                vec!["move", "names"],
+                vec!["accelerate", "draw_names"],
+                vec!["spawner"],
+                vec!["rename"],
+                vec!["late"],
+            ])
+        );
+        assert_eq!(
+            schedule.conflicting_systems(Stage::Update),
+            vec![("move", "accelerate"), ("move", "draw_names"), ("names", "rename"), ("accelerate", "late"), ("draw_names", "rename")]
+        );
+    }
+
+    struct Owner(Entity);
+    struct Label(PublicDataKey<String>);
+
+    #[test]
+    fn commands_are_applied_in_order_after_iterating(){
+        let mut world = World::new();
+        let mut commands = Commands::new();
+        for i in 0..4 {
+            let entity = world.spawn();
+            world.insert(entity, Transform { x: i as f32, y: 0.0 });
+        }
+
+        for (entity, transform) in world.query::<(Entity, &Transform)>() {
+            if transform.x >= 2.0 {
+                commands.despawn(entity);
+            } else {
+                commands
+                    .spawn()
+                    .insert(Velocity { x: transform.x, y: 0.0 })
+                    .insert(Owner(entity))
+                    .then(|world, child| {
+                        world.insert(child, Name("child"));
+                    });
+            }
+        }
+        assert_eq!(world.entity_count(), 4, "Commands should not be applied before calling apply");
+        assert_eq!(commands.len(), 10);
+
+        commands.apply(&mut world);
+        assert!(commands.is_empty());
+        assert_eq!(world.entity_count(), 4, "Two entities should be despawned and two spawned");
+        let mut children: Vec<(f32, Entity)> = world
+            .query::<(&Velocity, &Owner, &Name)>()
+            .map(|(velocity, owner, _)| (velocity.x, owner.0))
+            .collect();
+        children.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+        assert_eq!(children.len(), 2);
+        for (x, owner) in children {
+            assert_eq!(world.get::<Transform>(owner).map(|transform| transform.x), Some(x));
+        }
+    }
+
+    #[test]
+    fn commands_create_public_data_and_queue_the_created_event(){
+        let mut world = World::new();
+        let mut commands = Commands::new();
+        let entity = world.spawn();
+
+        commands.create_public_data(String::from("Inspector"), move |world, key| {
+            world.insert(entity, Label(key));
+        });
+        assert!(world.public_data().event_queue().is_empty());
+        commands.apply(&mut world);
+
+        let key = world.get::<Label>(entity).expect("The key should be stored in the entity").0;
+        assert_eq!(world.public_data().get(&key).map(String::as_str), Some("Inspector"));
+        assert_eq!(
+            world.public_data().event_queue().iter().copied().collect::<Vec<_>>(),
+            vec![PublicDataEvent::Created(key.id())]
+        );
+    }
+
+    #[test]
+    fn public_data_events_are_queued_once_until_they_are_cleared(){
+        let mut public_data = PublicDataSlotmap::new();
+        let name = public_data.create(String::from("Scene")).expect("The data should be created");
+        let size = public_data.create(10u32).expect("The data should be created");
+        assert!(!public_data.was_changed(&name));
+
+        public_data.get_mut(&name).expect("The data should exist").push_str(" 2");
+        public_data.get_mut(&name).expect("The data should exist");
+        assert!(public_data.was_changed(&name));
+        assert!(!public_data.was_changed(&size));
+        assert_eq!(public_data.destroy(size), Some(10));
+        assert!(public_data.get_mut(&size).is_none());
+
+        let events: Vec<PublicDataEvent> = public_data.drain_events().collect();
+        assert_eq!(events, vec![
+            PublicDataEvent::Created(name.id()),
+            PublicDataEvent::Created(size.id()),
+            PublicDataEvent::Changed(name.id()),
+            PublicDataEvent::Destroyed(size.id()),
+        ]);
+        assert!(events[3].id().downcast::<u32>().is_some());
+        assert!(events[3].id().downcast::<String>().is_none());
+
+        //After the flush a new mutable access queues the event again
+        assert!(!public_data.was_changed(&name));
+        public_data.get_mut(&name).expect("The data should exist");
+        assert!(public_data.was_changed(&name));
+        public_data.clear_events();
+        assert!(!public_data.was_changed(&name));
+        assert!(public_data.event_queue().is_empty());
+        assert_eq!(public_data.get(&name).map(String::as_str), Some("Scene 2"));
+    }
+
+    #[test]
+    fn public_data_events_are_flushed_with_the_world_trackers(){
+        let mut world = World::new();
+        let key = world.public_data_mut().create(1.5f32).expect("The data should be created");
+        *world.public_data_mut().get_mut(&key).expect("The data should exist") = 2.0;
+        assert_eq!(world.public_data().event_queue().len(), 2);
+
+        world.clear_trackers();
+        assert!(world.public_data().event_queue().is_empty());
+        assert!(!world.public_data().was_changed(&key));
+        assert_eq!(world.public_data().get(&key), Some(&2.0));
+    }
+
+    fn assert_near(a: Vec2, b: Vec2) {
+        assert!((a - b).length() < 1e-4, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn global_transforms_are_propagated_to_the_children(){
+        let mut world = World::new();
+        let root = world.spawn();
+        let child = world.spawn();
+        let grandchild = world.spawn();
+        world.insert(root, Transform2D {
+            position: Vec2::new(10.0, 0.0),
+            rotation: std::f32::consts::FRAC_PI_2,
+            scale: Vec2::splat(2.0),
+        });
+        world.insert(child, Transform2D::from_position(Vec2::new(1.0, 0.0)));
+        world.set_parent(child, root).unwrap();
+        world.set_parent(grandchild, child).unwrap();
+
+        world.propagate_transforms();
+
+        let global = world.get::<GlobalTransform2D>(child).unwrap().transform();
+        assert_near(global.position, Vec2::new(10.0, 2.0));
+        assert_near(global.scale, Vec2::splat(2.0));
+        //Entities without a local transform follow their parent
+        assert_eq!(
+            world.get::<GlobalTransform2D>(grandchild),
+            world.get::<GlobalTransform2D>(child)
+        );
+    }
+
+    #[test]
+    fn parents_without_a_transform_propagate_the_identity(){
+        let mut world = World::new();
+        let group = world.spawn();
+        let child = world.spawn();
+        let grandchild = world.spawn();
+        world.insert(child, Transform2D::from_position(Vec2::new(3.0, 4.0)));
+        world.set_parent(child, group).unwrap();
+        world.set_parent(grandchild, child).unwrap();
+
+        world.propagate_transforms();
+
+        assert_eq!(world.get::<GlobalTransform2D>(group), Some(&GlobalTransform2D(Transform2D::IDENTITY)));
+        let global = world.get::<GlobalTransform2D>(child).expect("The child should have a global transform");
+        assert_near(global.transform().position, Vec2::new(3.0, 4.0));
+        assert_eq!(world.get::<GlobalTransform2D>(grandchild), Some(global));
+    }
+
+    #[test]
+    fn despawned_children_are_removed_from_their_parent(){
+        let mut world = World::new();
+        let root = world.spawn();
+        let first = world.spawn();
+        let second = world.spawn();
+        world.set_parent(first, root).unwrap();
+        world.set_parent(second, root).unwrap();
+
+        assert!(world.despawn(first));
+        assert_eq!(world.children(root), &[second]);
+        assert!(world.despawn(second));
+        assert!(!world.has::<Children>(root));
+    }
+
+    #[test]
+    fn reparenting_moves_the_entity_and_rejects_cycles(){
+        let mut world = World::new();
+        let a = world.spawn();
+        let b = world.spawn();
+        let child = world.spawn();
+        world.set_parent(child, a).unwrap();
+        world.set_parent(child, b).unwrap();
+
+        assert_eq!(world.parent(child), Some(b));
+        assert!(world.children(a).is_empty());
+        assert_eq!(world.children(b), &[child]);
+
+        assert_eq!(world.set_parent(b, child), Err(HierarchyError::Cycle { child: b, parent: child }));
+        assert_eq!(world.set_parent(a, a), Err(HierarchyError::Cycle { child: a, parent: a }));
+        assert_eq!(world.remove_parent(child), Some(b));
+        assert!(world.children(b).is_empty());
+    }
+
+    #[test]
+    fn despawn_recursive_removes_the_descendants(){
+        let mut world = World::new();
+        let root = world.spawn();
+        let child = world.spawn();
+        let grandchild = world.spawn();
+        let sibling = world.spawn();
+        world.set_parent(child, root).unwrap();
+        world.set_parent(grandchild, child).unwrap();
+        world.set_parent(sibling, root).unwrap();
+
+        assert!(world.despawn_recursive(child));
+
+        assert!(!world.is_alive(child));
+        assert!(!world.is_alive(grandchild));
+        assert!(world.is_alive(sibling));
+        assert_eq!(world.children(root), &[sibling]);
+        assert!(!world.despawn_recursive(child));
+    }
+
+    #[test]
+    fn changes_are_tracked_until_the_trackers_are_cleared(){
+        let mut world = World::new();
+        let a = world.spawn();
+        let b = world.spawn();
+        world.insert(a, Transform { x: 0.0, y: 0.0 });
+        world.insert(b, Transform { x: 1.0, y: 0.0 });
+        assert_eq!(world.query::<(Entity, Added<Transform>)>().count(), 2);
+
+        world.clear_trackers();
+        assert!(!world.is_added::<Transform>(a));
+        assert_eq!(world.query::<(Entity, Changed<Transform>)>().count(), 0);
+
+        world.get_mut::<Transform>(b).unwrap().x = 2.0;
+        world.remove::<Transform>(a);
+        let changed: Vec<Entity> = world
+            .query::<(Entity, Changed<Transform>)>()
+            .map(|(entity, _)| entity)
+            .collect();
+        assert_eq!(changed, vec![b]);
+        assert!(!world.is_added::<Transform>(b));
+        assert_eq!(world.removed::<Transform>().collect::<Vec<_>>(), vec![a]);
+
+        world.despawn(b);
+        assert_eq!(world.removed::<Transform>().collect::<Vec<_>>(), vec![a, b]);
+        world.clear_trackers();
+        assert_eq!(world.removed::<Transform>().count(), 0);
+    }
+
+    #[test]
+    fn mutable_queries_only_mark_the_yielded_components_as_changed(){
+        let mut world = World::new();
+        let a = world.spawn();
+        let b = world.spawn();
+        world.insert(a, Transform { x: 0.0, y: 0.0 });
+        world.insert(b, Transform { x: 0.0, y: 0.0 });
+        world.insert(a, Velocity { x: 1.0, y: 0.0 });
+        //Both storages have two components, so the query goes through the entities with a transform
+        let c = world.spawn();
+        world.insert(c, Velocity { x: 1.0, y: 0.0 });
+        world.clear_trackers();
+
+        //The velocity is fetched after the transform, b is rejected once its transform was already found
+        assert_eq!(world.query::<(&mut Transform, &Velocity)>().count(), 1);
+        assert!(world.is_changed::<Transform>(a));
+        assert!(!world.is_changed::<Transform>(b));
+        let changed: Vec<Entity> = world
+            .query::<(Entity, Changed<Transform>)>()
+            .map(|(entity, _)| entity)
+            .collect();
+        assert_eq!(changed, vec![a]);
+    }
+
+    #[test]
+    fn hooks_run_when_components_are_added_and_removed(){
+        let mut world = World::new();
+        world.on_add::<Transform, _>(|world, entity| {
+            world.insert(entity, Velocity { x: 1.0, y: 1.0 });
+        });
+        world.on_remove::<Transform, _>(|world, entity| {
+            //The component can still be read by the hook
+            let x = world.get::<Transform>(entity).unwrap().x;
+            world.public_data_mut().create(x);
+        });
+
+        let entity = world.spawn();
+        world.insert(entity, Transform { x: 3.0, y: 0.0 });
+        assert!(world.has::<Velocity>(entity));
+        world.remove::<Velocity>(entity);
+        //Replacing the component does not run the hook again
+        world.insert(entity, Transform { x: 4.0, y: 0.0 });
+        assert!(!world.has::<Velocity>(entity));
+
+        world.despawn(entity);
+        let values: Vec<f32> = world.public_data().iter::<f32>().map(|(_, x)| *x).collect();
+        assert_eq!(values, vec![4.0]);
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Score(u32);
+
+    #[test]
+    fn resources_are_stored_once_per_type(){
+        let mut world = World::new();
+        assert_eq!(world.insert_resource(Score(1)), None);
+        assert_eq!(world.insert_resource(Score(2)), Some(Score(1)));
+        world.resource_mut::<Score>().unwrap().0 += 1;
+        assert_eq!(world.resource::<Score>(), Some(&Score(3)));
+        assert!(world.resource::<crate::Engine>().is_none());
+
+        let mut commands = Commands::new();
+        commands.remove_resource::<Score>();
+        commands.insert_resource(String::from("resource"));
+        commands.apply(&mut world);
+        assert!(!world.contains_resource::<Score>());
+        assert_eq!(world.resource::<String>().map(String::as_str), Some("resource"));
+    }
+
+    #[test]
+    fn parallel_systems_use_the_resources_they_declare(){
+        for execution_mode in [ExecutionMode::Parallel, ExecutionMode::SingleThreaded] {
+            let mut world = World::new();
+            world.insert_resource(Score(0));
+            let mut schedule = Schedule::new();
+            schedule.set_execution_mode(execution_mode);
+            schedule.add_system(
+                Stage::Update,
+                System::parallel("score", |world, _| {
+                    world.resource_mut::<Score>().unwrap().0 += 10;
+                })
+                .writes::<Score>(),
+            );
+            schedule.add_system(
+                Stage::Update,
+                System::parallel("reader", |world, _| {
+                    assert_eq!(world.resource::<Score>().map(|score| score.0), Some(10));
+                })
+                .reads::<Score>()
+                .after("score"),
+            );
+            schedule.initialize().unwrap();
+            assert_eq!(schedule.conflicting_systems(Stage::Update), vec![("score", "reader")]);
+
+            schedule.run_stage(Stage::Update, &mut world, &mut SystemContext::without_engine());
+            assert_eq!(world.resource::<Score>().map(|score| score.0), Some(10));
+        }
+    }
+
+    /// Exclusive systems, two parallel batches and the commands of a stage, every system logs when it runs
+    fn logging_schedule(log: &Arc<Mutex<Vec<&'static str>>>) -> Schedule {
+        let mut schedule = Schedule::new();
+        let logger = |name: &'static str| {
+            let log = log.clone();
+            move || log.lock().unwrap().push(name)
+        };
+
+        let log_spawn = logger("spawn");
+        schedule.add_system(
+            Stage::Update,
+            System::new("spawn", move |world, context| {
+                log_spawn();
+                assert_eq!(world.entity_count(), 1);
+                context.commands().spawn().insert(Velocity { x: 1.0, y: 0.0 });
+            }),
+        );
+        let log_move = logger("move");
+        schedule.add_system(
+            Stage::Update,
+            System::parallel("move", move |world, _| {
+                log_move();
+                for (transform, velocity) in world.query::<(&mut Transform, &Velocity)>() {
+                    transform.x += velocity.x;
+                }
+                std::thread::sleep(std::time::Duration::from_millis(2));
+            })
+            .writes::<Transform>()
+            .reads::<Velocity>()
+            .after("spawn"),
+        );
+        let log_count = logger("count");
+        schedule.add_system(
+            Stage::Update,
+            System::parallel("count", move |world, _| {
+                log_count();
+                //The entity spawned by the commands is not in the world until the stage ends
+                assert_eq!(world.query::<&Velocity>().count(), 1);
+                world.resource_mut::<Score>().unwrap().0 += 1;
+            })
+            .reads::<Velocity>()
+            .writes::<Score>()
+            .after("spawn"),
+        );
+        let log_report = logger("report");
+        schedule.add_system(
+            Stage::Update,
+            System::parallel("report", move |world, _| {
+                log_report();
+                let x = world.query::<&Transform>().next().map(|transform| transform.x);
+                assert_eq!(x, Some(1.0));
+            })
+            .reads::<Transform>()
+            .after("move"),
+        );
+        schedule
+    }
+
+    #[test]
+    fn stages_run_without_an_engine_in_both_execution_modes(){
+        for execution_mode in [ExecutionMode::Parallel, ExecutionMode::SingleThreaded] {
+            let log = Arc::new(Mutex::new(Vec::new()));
+            let mut schedule = logging_schedule(&log);
+            schedule.set_execution_mode(execution_mode);
+            assert_eq!(
+                schedule.system_batches(Stage::Update).unwrap(),
+                vec![vec!["spawn"], vec!["move", "count"], vec!["report"]]
+            );
+
+            let mut world = World::new();
+            world.insert_resource(Score(0));
+            let entity = world.spawn();
+            world.insert(entity, Transform { x: 0.0, y: 0.0 });
+            world.insert(entity, Velocity { x: 1.0, y: 0.0 });
+
+            let mut context = SystemContext::without_engine();
+            assert!(context.engine().is_none());
+            schedule.run_stage(Stage::Update, &mut world, &mut context);
+
+            let log = log.lock().unwrap().clone();
+            assert_eq!(log.len(), 4, "{:?}", execution_mode);
+            assert_eq!(log[0], "spawn");
+            assert_eq!(log[3], "report");
+            if execution_mode == ExecutionMode::SingleThreaded {
+                assert_eq!(log, vec!["spawn", "move", "count", "report"]);
+            }
+            //The commands were applied once the stage finished
+            assert_eq!(world.entity_count(), 2);
+            assert_eq!(world.query::<&Velocity>().count(), 2);
+            assert_eq!(world.resource::<Score>().map(|score| score.0), Some(1));
+
+            let timer = schedule.timer();
+            let move_time = timer.get_system_time("move").expect("The system time should be recorded");
+            assert!(move_time.0 >= 2_000);
+            assert!(timer.get_stage_time(Stage::Update).0 >= move_time.0);
+            assert_eq!(timer.system_times.len(), 4);
+            assert_eq!(timer.get_stage_time(Stage::Render).0, 0);
+        }
+    }
+
+    #[test]
+    fn fixed_update_times_add_up_over_the_steps_of_a_frame(){
+        let mut schedule = Schedule::new();
+        schedule.add_system(
+            Stage::FixedUpdate,
+            System::new("step", |_, _| std::thread::sleep(std::time::Duration::from_millis(2))),
+        );
+        let mut world = World::new();
+        let mut context = SystemContext::without_engine();
+
+        schedule.start_frame();
+        for _ in 0..3 {
+            schedule.run_stage(Stage::FixedUpdate, &mut world, &mut context);
+        }
+        let timer = schedule.timer();
+        assert_eq!(timer.fixed_steps, 3);
+        assert_eq!(timer.system_times.len(), 1, "Each system should only be recorded once per frame");
+        let step_time = timer.get_system_time("step").expect("The system time should be recorded");
+        assert!(step_time.0 >= 6_000, "The time of the three steps should add up");
+        assert!(timer.get_stage_time(Stage::FixedUpdate).0 >= step_time.0);
+
+        schedule.start_frame();
+        schedule.run_stage(Stage::Update, &mut world, &mut context);
+        let timer = schedule.timer();
+        assert_eq!(timer.fixed_steps, 0, "A frame without steps should not keep the steps of the previous frame");
+        assert_eq!(timer.get_stage_time(Stage::FixedUpdate).0, 0);
+        assert!(timer.get_system_time("step").is_none());
+    }
+
+    #[test]
+    fn commands_of_parallel_systems_are_applied_in_schedule_order(){
+        //The systems of the batch only overlap with several threads, the global pool may already exist
+        let _ = rayon::ThreadPoolBuilder::new().num_threads(4).build_global();
+        let applied = Arc::new(Mutex::new(Vec::new()));
+        let mut schedule = Schedule::new();
+        schedule.set_execution_mode(ExecutionMode::Parallel);
+        let names = ["first", "second", "third", "fourth"];
+        for (index, name) in names.into_iter().enumerate() {
+            let applied = applied.clone();
+            schedule.add_system(
+                Stage::Update,
+                System::parallel(name, move |_, context| {
+                    //The first systems finish last, so the threads add their commands in the reverse order
+                    std::thread::sleep(std::time::Duration::from_millis(10 * (names.len() - index) as u64));
+                    let applied = applied.clone();
+                    context
+                        .commands()
+                        .spawn()
+                        .insert(Name(name))
+                        .then(move |world, entity| {
+                            assert_eq!(world.get::<Name>(entity), Some(&Name(name)));
+                            applied.lock().unwrap().push(name);
+                        });
+                }),
+            );
+        }
+        assert_eq!(schedule.system_batches(Stage::Update).unwrap(), vec![names.to_vec()]);
+
+        let mut world = World::new();
+        let mut context = SystemContext::without_engine();
+        schedule.run_stage(Stage::Update, &mut world, &mut context);
+        assert_eq!(*applied.lock().unwrap(), names.to_vec());
+        assert_eq!(world.query::<&Name>().count(), 4);
+    }
+
+    fn run_frame(schedule: &mut Schedule, world: &mut World, fixed_steps: u32) {
+        let mut context = SystemContext::without_engine();
+        schedule.run_stage(Stage::Events, world, &mut context);
+        for _ in 0..fixed_steps {
+            schedule.run_stage(Stage::FixedUpdate, world, &mut context);
+        }
+        for stage in [Stage::Update, Stage::Render, Stage::FrameEnd] {
+            schedule.run_stage(stage, world, &mut context);
+        }
+        schedule.clear_trackers(world);
+    }
+
+    #[test]
+    fn systems_see_the_changes_made_after_they_last_ran(){
+        type Seen = Arc<Mutex<Vec<(&'static str, usize, usize)>>>;
+        let seen: Seen = Arc::new(Mutex::new(Vec::new()));
+        let mut world = World::new();
+        let entity = world.spawn();
+        world.insert(entity, Transform { x: 0.0, y: 0.0 });
+        world.insert(entity, Velocity { x: 1.0, y: 0.0 });
+        world.clear_trackers();
+
+        let mut schedule = Schedule::new();
+        let seen_fixed = seen.clone();
+        schedule.add_system(
+            Stage::FixedUpdate,
+            System::new("fixed", move |world, _| {
+                seen_fixed.lock().unwrap().push(("fixed", 0, world.removed::<Velocity>().count()));
+            }),
+        );
+        let seen_update = seen.clone();
+        schedule.add_system(
+            Stage::Update,
+            System::new("observe", move |world, _| {
+                let changed = world.query::<(Entity, Changed<Transform>)>().count();
+                let removed = world.removed::<Velocity>().count();
+                seen_update.lock().unwrap().push(("observe", changed, removed));
+            }),
+        );
+        let seen_parallel = seen.clone();
+        schedule.add_system(
+            Stage::Update,
+            System::parallel("observe_parallel", move |world, _| {
+                let changed = world.query::<(Entity, Changed<Transform>)>().count();
+                seen_parallel.lock().unwrap().push(("observe_parallel", changed, 0));
+            })
+            .reads::<Transform>(),
+        );
+        schedule.add_system(Stage::Update, System::parallel("idle", |_, _| {}));
+        let mut frame = 0;
+        schedule.add_system(
+            Stage::FrameEnd,
+            System::new("write", move |world, _| {
+                frame += 1;
+                if frame == 1 {
+                    world.get_mut::<Transform>(entity).unwrap().x = 1.0;
+                    world.remove::<Velocity>(entity);
+                }
+            }),
+        );
+        assert_eq!(
+            schedule.system_batches(Stage::Update).unwrap(),
+            vec![vec!["observe"], vec!["observe_parallel", "idle"]]
+        );
+
+        let mut take_seen = || std::mem::take(&mut *seen.lock().unwrap());
+        run_frame(&mut schedule, &mut world, 1);
+        assert_eq!(take_seen(), vec![("fixed", 0, 0), ("observe", 0, 0), ("observe_parallel", 0, 0)]);
+
+        //The frame end of the last frame ran after the update systems, they see its changes on the next frame
+        run_frame(&mut schedule, &mut world, 0);
+        assert_eq!(take_seen(), vec![("observe", 1, 1), ("observe_parallel", 1, 0)]);
+
+        //The fixed update system did not run on the last frame, so the removed component was kept for it
+        run_frame(&mut schedule, &mut world, 1);
+        assert_eq!(take_seen(), vec![("fixed", 0, 1), ("observe", 0, 0), ("observe_parallel", 0, 0)]);
+
+        run_frame(&mut schedule, &mut world, 1);
+        assert_eq!(take_seen(), vec![("fixed", 0, 0), ("observe", 0, 0), ("observe_parallel", 0, 0)]);
+    }
+}
@@ -0,0 +1,397 @@
+use std::{
+    any::TypeId,
+    borrow::Cow,
+    cell::UnsafeCell,
+    collections::HashMap,
+    marker::PhantomData,
+    sync::Arc,
+};
+
+use crate::{
+    slotmap::{create_custom_key, GrowthPolicy, Slotmap},
+    Engine,
+};
+
+use super::{
+    component::{AnyStorage, ComponentStorage},
+    public_data::PublicDataSlotmap,
+    query::{check_query_access, QueryIter, QueryParam},
+    resource::{Resource, Resources},
+    Component,
+};
+
+create_custom_key!(
+    /// Generational key of an entity, it becomes invalid when the entity is despawned
+    Entity;
+);
+
+/// Function that runs when a component is added to or removed from an entity
+pub type ComponentHook = Arc<dyn Fn(&mut World, Entity) + Send + Sync>;
+
+#[derive(Default)]
+struct ComponentHooks {
+    on_add: Vec<ComponentHook>,
+    on_remove: Vec<ComponentHook>,
+}
+
+/// Stores the entities and their components, each component type is stored densely in its own `ComponentStorage`.\
+/// The world also owns the Public Data Slotmap and the resources, so every system that gets the world can
+/// communicate through them
+pub struct World {
+    entities: Slotmap<(), Entity>,
+    storages: HashMap<TypeId, Box<UnsafeCell<dyn AnyStorage>>>,
+    public_data: PublicDataSlotmap,
+    resources: Resources,
+    /// Only set while `with_engine_resource` runs
+    engine: Option<*const Engine>,
+    hooks: HashMap<TypeId, ComponentHooks>,
+    change_tick: u32,
+    /// Tick of the current observer, the system that is running or the last `clear_trackers` outside of the systems
+    last_change_tick: u32,
+}
+
+//The storages are only modified through a shared reference by the unsafe query functions, their callers
+//have to make sure the accesses do not conflict
+unsafe impl Send for World {}
+unsafe impl Sync for World {}
+
+impl Default for World {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl World {
+    pub fn new() -> Self {
+        Self::with_capacity(64)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            entities: Slotmap::with_growth_policy(capacity, GrowthPolicy::Double),
+            storages: HashMap::new(),
+            public_data: PublicDataSlotmap::new(),
+            resources: Resources::new(),
+            engine: None,
+            hooks: HashMap::new(),
+            //The components inserted before the first `clear_trackers` are newer than the observer
+            change_tick: 1,
+            last_change_tick: 0,
+        }
+    }
+
+    pub fn spawn(&mut self) -> Entity {
+        self.entities
+            .push(())
+            .expect("The entity slotmap could not grow")
+    }
+
+    /// Removes the entity and all its components, returns `false` if the entity was not alive.\
+    /// The `on_remove` hooks of the components run before anything is removed
+    pub fn despawn(&mut self, entity: Entity) -> bool {
+        if !self.is_alive(entity) {
+            return false;
+        }
+        let hooked_components: Vec<TypeId> = self
+            .hooks
+            .iter()
+            .filter(|(_, hooks)| !hooks.on_remove.is_empty())
+            .map(|(type_id, _)| *type_id)
+            .collect();
+        for type_id in hooked_components {
+            let has_component = self
+                .storages
+                .get_mut(&type_id)
+                .is_some_and(|storage| storage.get_mut().contains_entity(entity));
+            if has_component {
+                self.run_hooks(type_id, entity, |hooks| &hooks.on_remove);
+            }
+        }
+
+        //A hook could have despawned the entity already
+        self.detach_from_parent(entity);
+        self.entities.remove(entity);
+        for storage in self.storages.values_mut() {
+            storage.get_mut().remove_entity(entity);
+        }
+        true
+    }
+
+    pub fn is_alive(&self, entity: Entity) -> bool {
+        self.entities.is_valid(&entity)
+    }
+
+    pub fn entity_count(&self) -> usize {
+        self.entities.len()
+    }
+
+    pub fn entities(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.entities.keys()
+    }
+
+    pub fn public_data(&self) -> &PublicDataSlotmap {
+        &self.public_data
+    }
+
+    pub fn public_data_mut(&mut self) -> &mut PublicDataSlotmap {
+        &mut self.public_data
+    }
+
+    /// Stores the resource, replacing the resource of the same type, which is returned
+    pub fn insert_resource<T: Resource>(&mut self, resource: T) -> Option<T> {
+        self.resources.insert(resource)
+    }
+
+    pub fn remove_resource<T: Resource>(&mut self) -> Option<T> {
+        self.resources.remove::<T>()
+    }
+
+    pub fn contains_resource<T: Resource>(&self) -> bool {
+        self.resource::<T>().is_some()
+    }
+
+    /// The `Engine` is also available here while the `ScheduleRuntime` exposes it, but only as a shared reference
+    pub fn resource<T: Resource>(&self) -> Option<&T> {
+        if let Some(resource) = self.resources.get::<T>() {
+            return Some(resource);
+        }
+        let engine = self.engine?;
+        //The engine pointer is only set while the engine is borrowed by 'with_engine_resource'
+        (TypeId::of::<T>() == TypeId::of::<Engine>()).then(|| unsafe { &*engine.cast::<T>() })
+    }
+
+    pub fn resource_mut<T: Resource>(&mut self) -> Option<&mut T> {
+        self.resources.get_mut::<T>()
+    }
+
+    pub fn resources(&self) -> &Resources {
+        &self.resources
+    }
+
+    /// Makes the engine available through `resource::<Engine>()` while the function runs
+    ///
+    /// # Safety
+    /// The engine cannot be modified or dropped while the function runs
+    pub(super) unsafe fn with_engine_resource<R>(
+        &mut self,
+        engine: *const Engine,
+        function: impl FnOnce(&mut World) -> R,
+    ) -> R {
+        //The pointer is removed even if the function panics
+        struct EngineGuard<'w>(&'w mut World);
+        impl Drop for EngineGuard<'_> {
+            fn drop(&mut self) {
+                self.0.engine = None;
+            }
+        }
+
+        self.engine = Some(engine);
+        let guard = EngineGuard(self);
+        function(&mut *guard.0)
+    }
+
+    /// Adds the component to the entity, replacing the component of the same type it had before.\
+    /// The `on_add` hooks run if the entity did not have the component.
+    /// Returns `false` if the entity is not alive
+    pub fn insert<T: Component>(&mut self, entity: Entity, component: T) -> bool {
+        if !self.is_alive(entity) {
+            return false;
+        }
+        let previous = self.storage_or_insert::<T>().insert(entity, component);
+        if previous.is_none() {
+            self.run_hooks(TypeId::of::<T>(), entity, |hooks| &hooks.on_add);
+        }
+        true
+    }
+
+    /// The `on_remove` hooks run before the component is removed, so they can still read it
+    pub fn remove<T: Component>(&mut self, entity: Entity) -> Option<T> {
+        if !self.has::<T>(entity) {
+            return None;
+        }
+        self.run_hooks(TypeId::of::<T>(), entity, |hooks| &hooks.on_remove);
+        self.storage_mut::<T>()?.remove(entity)
+    }
+
+    /// Registers a function that runs every time the component is added to an entity that did not have it.\
+    /// Hooks only run for changes made through the world, not through `storage_mut`
+    pub fn on_add<T, F>(&mut self, hook: F)
+    where
+        T: Component,
+        F: Fn(&mut World, Entity) + Send + Sync + 'static,
+    {
+        self.hooks
+            .entry(TypeId::of::<T>())
+            .or_default()
+            .on_add
+            .push(Arc::new(hook));
+    }
+
+    /// Registers a function that runs every time the component is removed from an entity,
+    /// including when the entity is despawned
+    pub fn on_remove<T, F>(&mut self, hook: F)
+    where
+        T: Component,
+        F: Fn(&mut World, Entity) + Send + Sync + 'static,
+    {
+        self.hooks
+            .entry(TypeId::of::<T>())
+            .or_default()
+            .on_remove
+            .push(Arc::new(hook));
+    }
+
+    /// The hooks are cloned first, so they can register other hooks or change the world freely
+    fn run_hooks<F>(&mut self, type_id: TypeId, entity: Entity, select: F)
+    where
+        F: Fn(&ComponentHooks) -> &Vec<ComponentHook>,
+    {
+        let hooks = match self.hooks.get(&type_id) {
+            Some(hooks) if !select(hooks).is_empty() => select(hooks).clone(),
+            _ => return,
+        };
+        for hook in hooks {
+            hook(self, entity);
+        }
+    }
+
+    /// Returns `true` if the component was inserted since the running system last ran,
+    /// or since the trackers were cleared outside of the systems
+    pub fn is_added<T: Component>(&self, entity: Entity) -> bool {
+        self.storage::<T>()
+            .is_some_and(|storage| storage.is_added(entity))
+    }
+
+    /// Returns `true` if the component was inserted or accessed mutably since the running system last ran,
+    /// or since the trackers were cleared outside of the systems
+    pub fn is_changed<T: Component>(&self, entity: Entity) -> bool {
+        self.storage::<T>()
+            .is_some_and(|storage| storage.is_changed(entity))
+    }
+
+    /// The entities that lost the component since the running system last ran, including the despawned entities
+    pub fn removed<T: Component>(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.storage::<T>()
+            .into_iter()
+            .flat_map(|storage| storage.removed())
+    }
+
+    /// Tick the current changes are stamped with
+    pub fn change_tick(&self) -> u32 {
+        self.change_tick
+    }
+
+    /// Tick of the current observer, only the changes newer than it are reported
+    pub fn last_change_tick(&self) -> u32 {
+        self.last_change_tick
+    }
+
+    /// Makes the changes newer than the tick visible, the schedule sets it to the last run of each system
+    pub(super) fn set_last_change_tick(&mut self, last_change_tick: u32) {
+        self.last_change_tick = last_change_tick;
+        self.update_storage_ticks();
+    }
+
+    /// The changes made after this are newer than the systems that already ran
+    pub(super) fn increment_change_tick(&mut self) {
+        self.change_tick = self.change_tick.wrapping_add(1);
+        self.update_storage_ticks();
+    }
+
+    fn update_storage_ticks(&mut self) {
+        for storage in self.storages.values_mut() {
+            storage
+                .get_mut()
+                .set_change_ticks(self.change_tick, self.last_change_tick);
+        }
+    }
+
+    /// Starts a new frame for the change detection, and clears the events of the Public Data Slotmap.\
+    /// Outside of a schedule the world is the only observer, so all the removed entities are forgotten
+    pub fn clear_trackers(&mut self) {
+        self.clear_trackers_seen_by(self.change_tick);
+    }
+
+    /// Same as `clear_trackers`, but the removed entities newer than the oldest observer are kept
+    pub(super) fn clear_trackers_seen_by(&mut self, oldest_last_run_tick: u32) {
+        for storage in self.storages.values_mut() {
+            storage.get_mut().forget_removed(oldest_last_run_tick);
+        }
+        self.last_change_tick = self.change_tick;
+        self.increment_change_tick();
+        self.public_data.clear_events();
+    }
+
+    pub fn has<T: Component>(&self, entity: Entity) -> bool {
+        self.storage::<T>()
+            .is_some_and(|storage| storage.contains(entity))
+    }
+
+    pub fn get<T: Component>(&self, entity: Entity) -> Option<&T> {
+        self.storage::<T>()?.get(entity)
+    }
+
+    pub fn get_mut<T: Component>(&mut self, entity: Entity) -> Option<&mut T> {
+        self.storage_mut::<T>()?.get_mut(entity)
+    }
+
+    pub fn storage<T: Component>(&self) -> Option<&ComponentStorage<T>> {
+        //No mutable references to the storages can exist while the world is borrowed
+        self.storage_ptr::<T>().map(|storage| unsafe { &*storage })
+    }
+
+    pub fn storage_mut<T: Component>(&mut self) -> Option<&mut ComponentStorage<T>> {
+        self.storage_ptr::<T>().map(|storage| unsafe { &mut *storage })
+    }
+
+    fn storage_or_insert<T: Component>(&mut self) -> &mut ComponentStorage<T> {
+        let (change_tick, last_change_tick) = (self.change_tick, self.last_change_tick);
+        self.storages
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| {
+                Box::new(UnsafeCell::new(ComponentStorage::<T>::with_change_ticks(
+                    change_tick,
+                    last_change_tick,
+                )))
+            })
+            .get_mut()
+            .as_any_mut()
+            .downcast_mut()
+            .expect("Component storage does not have the type of its key")
+    }
+
+    /// The storages are stored by the `TypeId` of their component, so the pointer can be cast without checking
+    pub(super) fn storage_ptr<T: Component>(&self) -> Option<*mut ComponentStorage<T>> {
+        self.storages
+            .get(&TypeId::of::<T>())
+            .map(|storage| storage.get().cast::<ComponentStorage<T>>())
+    }
+
+    /// Iterates over all the entities that have the components of the query, e.g.
+    /// `world.query::<(&mut Transform, &Velocity)>()`.\
+    /// Panics if the query accesses a component mutably more than once
+    pub fn query<Q: QueryParam>(&mut self) -> QueryIter<'_, Q> {
+        //The world is borrowed mutably, so the query is the only access to the storages
+        unsafe { self.query_unchecked::<Q>(self.last_change_tick) }
+    }
+
+    /// Same as `query`, but through a shared reference so queries without conflicting accesses can run at the same time
+    ///
+    /// # Safety
+    /// No other reference can access the components the query writes, or write the components the query reads,
+    /// while the iterator or its items are alive. The entities and components cannot be added or removed either
+    pub(crate) unsafe fn query_unchecked<Q: QueryParam>(&self, last_run_tick: u32) -> QueryIter<'_, Q> {
+        check_query_access::<Q>();
+        let fetch = Q::fetch(self, last_run_tick);
+        let entities = match fetch.and_then(|fetch| Q::entities(fetch)) {
+            Some(entities) => Cow::Borrowed(entities),
+            None => Cow::Owned(self.entities.keys().collect()),
+        };
+        QueryIter {
+            entities,
+            next_index: 0,
+            fetch,
+            world: PhantomData,
+        }
+    }
+}
@@ -75,7 +75,7 @@ impl CopyTextureToSurface {
                         module: &copy_texture_shader,
                         targets: &[Some(wgpu::ColorTargetState {
                             write_mask: wgpu::ColorWrites::ALL,
-                            format: graphics.render_window.config.format,
+                            format: graphics.render_target.format(),
                             blend: Some(wgpu::BlendState::ALPHA_BLENDING),
                         })],
                     }),
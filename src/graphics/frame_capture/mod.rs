@@ -0,0 +1,298 @@
+use std::{
+    fmt,
+    io::{BufWriter, Write},
+    num::NonZeroU32,
+    path::{Path, PathBuf},
+    sync::mpsc,
+};
+
+use glam::UVec2;
+
+use super::{render_target::RenderTarget, render_texture::RenderTexture, Graphics};
+
+#[derive(Debug)]
+pub enum CaptureError {
+    /// Only the 8 bit RGBA/BGRA and the `Rgba16Float` formats can be captured
+    UnsupportedFormat(wgpu::TextureFormat),
+    /// The surface textures of a window cannot be copied, only the render textures
+    SurfaceTarget,
+    /// Textures with a width or height of 0 have no pixels to capture
+    EmptyTexture,
+    /// The padded rows are shorter than the pixels of a row, or the data is too short for all the rows
+    InvalidBufferLayout {
+        padded_bytes_per_row: u32,
+        row_size: usize,
+        rows: u32,
+        len: usize,
+    },
+    BufferMap(wgpu::BufferAsyncError),
+    Render(wgpu::SurfaceError),
+    Io(std::io::Error),
+    Encode(png::EncodingError),
+}
+
+impl fmt::Display for CaptureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CaptureError::UnsupportedFormat(format) => {
+                write!(f, "Textures with the format {:?} cannot be captured", format)
+            }
+            CaptureError::SurfaceTarget => write!(f, "The surface of a window cannot be captured"),
+            CaptureError::EmptyTexture => write!(f, "Textures without any pixels cannot be captured"),
+            CaptureError::InvalidBufferLayout {
+                padded_bytes_per_row,
+                row_size,
+                rows,
+                len,
+            } => write!(
+                f,
+                "{} bytes with rows padded to {} bytes cannot hold {} rows of {} bytes",
+                len, padded_bytes_per_row, rows, row_size
+            ),
+            CaptureError::BufferMap(error) => write!(f, "The capture buffer could not be mapped: {}", error),
+            CaptureError::Render(error) => write!(f, "The frame could not be rendered: {}", error),
+            CaptureError::Io(error) => write!(f, "The image could not be written: {}", error),
+            CaptureError::Encode(error) => write!(f, "The image could not be encoded: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for CaptureError {}
+
+impl From<std::io::Error> for CaptureError {
+    fn from(error: std::io::Error) -> Self {
+        CaptureError::Io(error)
+    }
+}
+
+impl From<png::EncodingError> for CaptureError {
+    fn from(error: png::EncodingError) -> Self {
+        CaptureError::Encode(error)
+    }
+}
+
+impl From<wgpu::SurfaceError> for CaptureError {
+    fn from(error: wgpu::SurfaceError) -> Self {
+        CaptureError::Render(error)
+    }
+}
+
+/// Pixels of a texture read back from the GPU, 8 bit RGBA with the rows from top to bottom
+pub struct CapturedFrame {
+    pub size: UVec2,
+    pub rgba: Vec<u8>,
+}
+
+impl CapturedFrame {
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let index = ((y * self.size.x + x) * 4) as usize;
+        [
+            self.rgba[index],
+            self.rgba[index + 1],
+            self.rgba[index + 2],
+            self.rgba[index + 3],
+        ]
+    }
+
+    pub fn to_png(&self) -> Result<Vec<u8>, CaptureError> {
+        let mut png = Vec::new();
+        self.write_png(&mut png)?;
+        Ok(png)
+    }
+
+    pub fn save_png(&self, path: impl AsRef<Path>) -> Result<(), CaptureError> {
+        let mut file = BufWriter::new(std::fs::File::create(path)?);
+        self.write_png(&mut file)?;
+        file.flush()?;
+        Ok(())
+    }
+
+    /// The fast compression is used, so recording frames does not slow down the headless loop too much
+    fn write_png(&self, writer: impl Write) -> Result<(), CaptureError> {
+        let mut encoder = png::Encoder::new(writer, self.size.x, self.size.y);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_compression(png::Compression::Fast);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.rgba)?;
+        writer.finish()?;
+        Ok(())
+    }
+}
+
+/// Size of a pixel for the formats that can be captured
+pub fn bytes_per_pixel(format: wgpu::TextureFormat) -> Option<u32> {
+    match format {
+        wgpu::TextureFormat::Rgba8Unorm
+        | wgpu::TextureFormat::Rgba8UnormSrgb
+        | wgpu::TextureFormat::Bgra8Unorm
+        | wgpu::TextureFormat::Bgra8UnormSrgb => Some(4),
+        wgpu::TextureFormat::Rgba16Float => Some(8),
+        _ => None,
+    }
+}
+
+/// The rows of a texture copied to a buffer have to be aligned to `wgpu::COPY_BYTES_PER_ROW_ALIGNMENT` (256) bytes
+pub fn padded_bytes_per_row(width: u32, bytes_per_pixel: u32) -> u32 {
+    let alignment = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+    let bytes_per_row = width * bytes_per_pixel;
+    bytes_per_row.div_ceil(alignment) * alignment
+}
+
+/// Removes the padding of the rows and converts the pixels to 8 bit RGBA.\
+/// The `Rgba16Float` values are clamped to [0, 1], there is no tone mapping
+pub fn convert_to_rgba8(
+    format: wgpu::TextureFormat,
+    size: UVec2,
+    padded_bytes_per_row: u32,
+    data: &[u8],
+) -> Result<Vec<u8>, CaptureError> {
+    let bytes_per_pixel = bytes_per_pixel(format).ok_or(CaptureError::UnsupportedFormat(format))?;
+    if size.x == 0 || size.y == 0 {
+        return Err(CaptureError::EmptyTexture);
+    }
+    let row_size = (size.x * bytes_per_pixel) as usize;
+    //The last row does not need its padding
+    let required_len = padded_bytes_per_row as usize * (size.y - 1) as usize + row_size;
+    if (padded_bytes_per_row as usize) < row_size || data.len() < required_len {
+        return Err(CaptureError::InvalidBufferLayout {
+            padded_bytes_per_row,
+            row_size,
+            rows: size.y,
+            len: data.len(),
+        });
+    }
+    let mut rgba = Vec::with_capacity((size.x * size.y * 4) as usize);
+    for row in data.chunks(padded_bytes_per_row as usize).take(size.y as usize) {
+        let row = &row[..row_size];
+        match format {
+            wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb => {
+                for pixel in row.chunks_exact(4) {
+                    rgba.extend_from_slice(&[pixel[2], pixel[1], pixel[0], pixel[3]]);
+                }
+            }
+            wgpu::TextureFormat::Rgba16Float => {
+                for channel in row.chunks_exact(2) {
+                    let value = half::f16::from_le_bytes([channel[0], channel[1]]).to_f32();
+                    rgba.push((value.clamp(0.0, 1.0) * 255.0).round() as u8);
+                }
+            }
+            _ => rgba.extend_from_slice(row),
+        }
+    }
+    Ok(rgba)
+}
+
+impl Graphics {
+    /// Copies the texture to a buffer and waits until the GPU is done to read it back.\
+    /// The texture needs the `COPY_SRC` usage, all the render textures have it
+    pub fn capture_texture(
+        &self,
+        texture: &wgpu::Texture,
+        format: wgpu::TextureFormat,
+        size: UVec2,
+    ) -> Result<CapturedFrame, CaptureError> {
+        let bytes_per_pixel = bytes_per_pixel(format).ok_or(CaptureError::UnsupportedFormat(format))?;
+        if size.x == 0 || size.y == 0 {
+            return Err(CaptureError::EmptyTexture);
+        }
+        let padded_bytes_per_row = padded_bytes_per_row(size.x, bytes_per_pixel);
+        let buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Capture Buffer"),
+            size: (padded_bytes_per_row * size.y) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Capture Encoder"),
+            });
+        encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: NonZeroU32::new(padded_bytes_per_row),
+                    rows_per_image: NonZeroU32::new(size.y),
+                },
+            },
+            wgpu::Extent3d {
+                width: size.x,
+                height: size.y,
+                depth_or_array_layers: 1,
+            },
+        );
+        self.queue.submit(Some(encoder.finish()));
+
+        let buffer_slice = buffer.slice(..);
+        let (sender, receiver) = mpsc::channel();
+        buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
+            //The receiver is still waiting, it cannot be dropped
+            sender.send(result).ok();
+        });
+        self.device.poll(wgpu::Maintain::Wait);
+        receiver
+            .recv()
+            .expect("The map callback was not called after waiting for the device")
+            .map_err(CaptureError::BufferMap)?;
+
+        let rgba = convert_to_rgba8(format, size, padded_bytes_per_row, &buffer_slice.get_mapped_range())?;
+        buffer.unmap();
+        Ok(CapturedFrame { size, rgba })
+    }
+
+    pub fn capture_render_texture(&self, render_texture: &RenderTexture) -> Result<CapturedFrame, CaptureError> {
+        self.capture_texture(&render_texture.texture, render_texture.format, render_texture.size)
+    }
+
+    /// Captures the last frame rendered by a headless engine
+    pub fn capture_render_target(&self) -> Result<CapturedFrame, CaptureError> {
+        match &self.render_target {
+            RenderTarget::Surface(_) => Err(CaptureError::SurfaceTarget),
+            RenderTarget::Texture(render_texture) => self.capture_render_texture(render_texture),
+        }
+    }
+}
+
+/// Writes the frames as numbered PNG files, `{name}_00000.png`, `{name}_00001.png`, ...
+pub struct ImageSequence {
+    pub directory: PathBuf,
+    pub name: String,
+    next_frame: u32,
+}
+
+impl ImageSequence {
+    /// The directory is created if it does not exist
+    pub fn new(directory: impl Into<PathBuf>, name: &str) -> Result<Self, CaptureError> {
+        let directory = directory.into();
+        std::fs::create_dir_all(&directory)?;
+        Ok(Self {
+            directory,
+            name: name.to_string(),
+            next_frame: 0,
+        })
+    }
+
+    /// Number of the next frame saved
+    pub fn next_frame(&self) -> u32 {
+        self.next_frame
+    }
+
+    pub fn frame_path(&self, frame: u32) -> PathBuf {
+        self.directory.join(format!("{}_{:05}.png", self.name, frame))
+    }
+
+    /// Returns the path of the file
+    pub fn save_frame(&mut self, frame: &CapturedFrame) -> Result<PathBuf, CaptureError> {
+        let path = self.frame_path(self.next_frame);
+        frame.save_png(&path)?;
+        self.next_frame += 1;
+        Ok(path)
+    }
+}
+
+#[cfg(test)]
+mod test;
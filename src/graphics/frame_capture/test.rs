@@ -0,0 +1,86 @@
+#[cfg(test)]
+mod tests {
+    use glam::uvec2;
+
+    use crate::graphics::frame_capture::{
+        convert_to_rgba8, padded_bytes_per_row, CaptureError, CapturedFrame,
+    };
+
+    #[test]
+    fn rows_are_aligned_to_256_bytes(){
+        assert_eq!(padded_bytes_per_row(1, 4), 256);
+        assert_eq!(padded_bytes_per_row(64, 4), 256);
+        assert_eq!(padded_bytes_per_row(65, 4), 512);
+        assert_eq!(padded_bytes_per_row(33, 8), 512);
+    }
+
+    #[test]
+    fn padding_is_removed_and_pixels_are_converted_to_rgba8(){
+        let size = uvec2(2, 2);
+        let mut bgra = vec![0u8; 256 * 2];
+        bgra[..8].copy_from_slice(&[1, 2, 3, 4, 5, 6, 7, 8]);
+        bgra[256..264].copy_from_slice(&[9, 10, 11, 12, 13, 14, 15, 16]);
+        let rgba = convert_to_rgba8(wgpu::TextureFormat::Bgra8Unorm, size, 256, &bgra).unwrap();
+        assert_eq!(rgba, vec![3, 2, 1, 4, 7, 6, 5, 8, 11, 10, 9, 12, 15, 14, 13, 16]);
+
+        let mut rgba16 = vec![0u8; 256];
+        for (index, value) in [1.0f32, 0.5, -1.0, 2.0].into_iter().enumerate() {
+            let bytes = half::f16::from_f32(value).to_le_bytes();
+            rgba16[index * 2..index * 2 + 2].copy_from_slice(&bytes);
+        }
+        let rgba = convert_to_rgba8(wgpu::TextureFormat::Rgba16Float, uvec2(1, 1), 256, &rgba16).unwrap();
+        assert_eq!(rgba, vec![255, 128, 0, 255]);
+
+        let result = convert_to_rgba8(wgpu::TextureFormat::R8Uint, size, 256, &bgra);
+        assert!(matches!(result, Err(CaptureError::UnsupportedFormat(_))));
+    }
+
+    #[test]
+    fn textures_without_pixels_are_rejected(){
+        let data = vec![0u8; 256];
+        for size in [uvec2(0, 1), uvec2(1, 0), uvec2(0, 0)] {
+            let result = convert_to_rgba8(wgpu::TextureFormat::Rgba8Unorm, size, 256, &data);
+            assert!(matches!(result, Err(CaptureError::EmptyTexture)));
+        }
+    }
+
+    #[test]
+    fn buffers_that_cannot_hold_the_rows_are_rejected(){
+        let size = uvec2(2, 2);
+        let format = wgpu::TextureFormat::Rgba8Unorm;
+        //The last row does not need its padding
+        let data = vec![0u8; 256 + 8];
+        assert_eq!(convert_to_rgba8(format, size, 256, &data).unwrap().len(), 16);
+
+        let result = convert_to_rgba8(format, size, 256, &data[..256 + 7]);
+        assert!(
+            matches!(result, Err(CaptureError::InvalidBufferLayout { len: 263, .. })),
+            "A short last row should be rejected"
+        );
+        let result = convert_to_rgba8(format, size, 256, &data[..256]);
+        assert!(matches!(result, Err(CaptureError::InvalidBufferLayout { .. })), "A missing row should be rejected");
+
+        let result = convert_to_rgba8(format, size, 4, &data);
+        assert!(
+            matches!(result, Err(CaptureError::InvalidBufferLayout { padded_bytes_per_row: 4, row_size: 8, .. })),
+            "Padded rows shorter than the pixels should be rejected"
+        );
+    }
+
+    #[test]
+    fn frames_are_encoded_as_rgba8_png_files(){
+        let frame = CapturedFrame {
+            size: uvec2(2, 1),
+            rgba: vec![255, 0, 0, 255, 0, 255, 0, 128],
+        };
+        assert_eq!(frame.pixel(1, 0), [0, 255, 0, 128]);
+
+        let png = frame.to_png().unwrap();
+        let mut reader = png::Decoder::new(png.as_slice()).read_info().unwrap();
+        let mut pixels = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut pixels).unwrap();
+        assert_eq!((info.width, info.height), (2, 1));
+        assert_eq!((info.color_type, info.bit_depth), (png::ColorType::Rgba, png::BitDepth::Eight));
+        assert_eq!(pixels[..info.buffer_size()], frame.rgba);
+    }
+}
@@ -1,22 +1,30 @@
 mod render_surface;
-use std::{borrow::Cow, cell::RefCell};
+use std::{borrow::Cow, fmt, sync::Mutex};
 
+pub mod frame_capture;
+pub mod render_target;
 pub mod render_texture;
 pub mod texture;
 use glam::{uvec2, UVec2};
 use render_surface::RenderSurface;
+use render_target::RenderTarget;
+use render_texture::RenderTexture;
 use wgpu::{util::DeviceExt, ColorTargetState, VertexBufferLayout};
 use winit::event::WindowEvent;
 
 use crate::EngineEvent;
 pub mod copy_texture_to_surface;
+/// Format of the render texture used by the headless graphics
+pub const HEADLESS_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
 pub struct Graphics {
-    pub render_window: RenderSurface,
+    pub render_target: RenderTarget,
     pub device: wgpu::Device,
     pub queue: wgpu::Queue,
-    /// It is a refcell because I don't want to give out mutable references to the entire render system
-    /// just because I need a mutable reference to be able to add / consume the destroy texture queue
-    destroy_texture_queue: RefCell<Vec<wgpu::Texture>>,
+    /// It is a mutex because I don't want to give out mutable references to the entire render system
+    /// just because I need a mutable reference to be able to add / consume the destroy texture queue.\
+    /// A mutex instead of a refcell keeps the engine `Sync`, so systems running in parallel can share it
+    destroy_texture_queue: Mutex<Vec<wgpu::Texture>>,
 }
 
 pub enum TextureSamplerType {
@@ -24,6 +32,26 @@ pub enum TextureSamplerType {
     ClampToEdge,
 }
 
+#[derive(Debug)]
+pub enum GraphicsError {
+    /// Neither a hardware adapter nor the fallback (software) adapter are available
+    AdapterNotFound,
+    RequestDevice(wgpu::RequestDeviceError),
+}
+
+impl fmt::Display for GraphicsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GraphicsError::AdapterNotFound => write!(f, "No graphics adapter could be found"),
+            GraphicsError::RequestDevice(error) => {
+                write!(f, "Device and Queue could not be created: {}", error)
+            }
+        }
+    }
+}
+
+impl std::error::Error for GraphicsError {}
+
 impl Graphics {
     pub async fn new(window: &winit::window::Window) -> Self {
         // The instance is a handle to our GPU
@@ -54,15 +82,61 @@ impl Graphics {
         let size = uvec2(window.inner_size().width, window.inner_size().height);
         let format = surface.get_supported_formats(&adapter)[0];
         let render_window = RenderSurface::new(surface, &device, size, format).await;
-        let destroy_texture_queue = RefCell::new(Vec::<wgpu::Texture>::with_capacity(20));
+        let destroy_texture_queue = Mutex::new(Vec::<wgpu::Texture>::with_capacity(20));
 
         Self {
-            render_window,
+            render_target: RenderTarget::Surface(render_window),
             destroy_texture_queue,
             device,
             queue,
         }
     }
+
+    /// Creates the graphics without a window, the frames are rendered to a texture of `HEADLESS_FORMAT`.\
+    /// If there is no hardware adapter the fallback adapter is used, so it also works on machines without a GPU
+    pub async fn new_headless(size: UVec2) -> Result<Self, GraphicsError> {
+        let instance = wgpu::Instance::new(wgpu::Backends::all());
+        let mut adapter = None;
+        for force_fallback_adapter in [false, true] {
+            adapter = instance
+                .request_adapter(&wgpu::RequestAdapterOptions {
+                    power_preference: wgpu::PowerPreference::HighPerformance,
+                    compatible_surface: None,
+                    force_fallback_adapter,
+                })
+                .await;
+            if adapter.is_some() {
+                break;
+            }
+        }
+        let adapter = adapter.ok_or(GraphicsError::AdapterNotFound)?;
+
+        let (device, queue) = adapter
+            .request_device(
+                &wgpu::DeviceDescriptor {
+                    features: wgpu::Features::empty(),
+                    //The fallback adapters might not support the default limits
+                    limits: wgpu::Limits::downlevel_defaults().using_resolution(adapter.limits()),
+                    label: None,
+                },
+                None, // Trace path
+            )
+            .await
+            .map_err(GraphicsError::RequestDevice)?;
+
+        Ok(Self {
+            render_target: RenderTarget::Texture(RenderTexture::from_device(
+                HEADLESS_FORMAT,
+                size,
+                &device,
+                "Headless Render Texture",
+                "Headless Render Texture View",
+            )),
+            destroy_texture_queue: Mutex::new(Vec::<wgpu::Texture>::with_capacity(20)),
+            device,
+            queue,
+        })
+    }
     pub fn resize_event_transformation(event: &EngineEvent) -> Option<UVec2> {
         match event {
             EngineEvent::WinitEvent(WindowEvent::Resized(physical_size)) => {
@@ -110,7 +184,9 @@ impl Graphics {
     }
 
     pub fn configure_surface(&mut self) {
-        self.render_window.configure_surface(&self.device);
+        if let RenderTarget::Surface(render_window) = &mut self.render_target {
+            render_window.configure_surface(&self.device);
+        }
     }
 
     pub fn create_shader_module_from_string(
@@ -177,15 +253,34 @@ impl Graphics {
     }
 
     pub fn resize(&mut self, new_size: UVec2){
-        self.render_window.resize(&self.device, new_size);
+        match &mut self.render_target {
+            RenderTarget::Surface(render_window) => render_window.resize(&self.device, new_size),
+            RenderTarget::Texture(render_texture) => {
+                if new_size != render_texture.size && new_size.x > 0 && new_size.y > 0 {
+                    let destroyed_texture = render_texture.replace_texture(new_size, &self.device);
+                    self.destroy_texture_queue
+                        .lock()
+                        .expect("Destroy texture queue was poisoned")
+                        .push(destroyed_texture);
+                }
+            }
+        }
     }
 
     pub fn queue_destroy_texture(&self, texture: wgpu::Texture) {
-        self.destroy_texture_queue.borrow_mut().push(texture);
+        self.destroy_texture_queue
+            .lock()
+            .expect("Destroy texture queue was poisoned")
+            .push(texture);
     }
 
     pub fn destroy_queued_textures(&self) {
-        for texture in self.destroy_texture_queue.borrow_mut().drain(..) {
+        for texture in self
+            .destroy_texture_queue
+            .lock()
+            .expect("Destroy texture queue was poisoned")
+            .drain(..)
+        {
             texture.destroy();
         }
     }
@@ -0,0 +1,37 @@
+use glam::UVec2;
+
+use super::{render_surface::RenderSurface, render_texture::RenderTexture};
+
+/// Where the frames are rendered, the surface of a window or a texture when the engine runs without a window
+pub enum RenderTarget {
+    Surface(RenderSurface),
+    Texture(RenderTexture),
+}
+
+impl RenderTarget {
+    pub fn size(&self) -> UVec2 {
+        match self {
+            RenderTarget::Surface(surface) => surface.size,
+            RenderTarget::Texture(texture) => texture.size,
+        }
+    }
+
+    pub fn format(&self) -> wgpu::TextureFormat {
+        match self {
+            RenderTarget::Surface(surface) => surface.config.format,
+            RenderTarget::Texture(texture) => texture.format,
+        }
+    }
+
+    pub fn is_headless(&self) -> bool {
+        matches!(self, RenderTarget::Texture(_))
+    }
+
+    /// The render texture, `None` when rendering to a window
+    pub fn texture(&self) -> Option<&RenderTexture> {
+        match self {
+            RenderTarget::Surface(_) => None,
+            RenderTarget::Texture(texture) => Some(texture),
+        }
+    }
+}
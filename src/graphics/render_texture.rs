@@ -1,9 +1,11 @@
 use crate::{
     graphics::{texture, Graphics},
-    slotmap::{Slotmap, SlotKey},
+    slotmap::{create_custom_key, Slotmap},
 };
 use glam::{UVec2, Vec2};
 
+create_custom_key!(RenderTextureKey;);
+
 pub struct RenderTexture {
     pub format: wgpu::TextureFormat,
     pub size: UVec2,
@@ -20,6 +22,18 @@ impl RenderTexture {
         size: UVec2,
         graphics: &Graphics,
 
+        texture_name: &str,
+        texture_view_name: &str
+    ) -> Self {
+        Self::from_device(format, size, &graphics.device, texture_name, texture_view_name)
+    }
+
+    /// Same as `new`, used while the `Graphics` are being created
+    pub(crate) fn from_device(
+        format: wgpu::TextureFormat,
+        size: UVec2,
+        device: &wgpu::Device,
+
         texture_name: &str,
         texture_view_name: &str
     ) -> Self {
@@ -34,7 +48,7 @@ impl RenderTexture {
             ..Default::default()
         };
 
-        let texture = graphics.device.create_texture(&texture_descriptor);
+        let texture = device.create_texture(&texture_descriptor);
 
         let texture_view = texture.create_view(&texture_view_descriptor);
 
@@ -56,8 +70,8 @@ impl RenderTexture {
         texture_name: &str,
         texture_view_name: &str,
 
-        render_texture_slotmap: &mut Slotmap<RenderTexture>,
-    ) -> Option<SlotKey> {
+        render_texture_slotmap: &mut Slotmap<RenderTexture, RenderTextureKey>,
+    ) -> Option<RenderTextureKey> {
         let render_texture =
             Self::new(format, size, render_system, texture_name, texture_view_name);
         let push_result = render_texture_slotmap.push(render_texture);
@@ -78,16 +92,23 @@ impl RenderTexture {
     }
 
     pub fn resize_texture(&mut self, new_size: UVec2, graphics: &Graphics) {
+        let destroyed_texture = self.replace_texture(new_size, &graphics.device);
+        graphics.queue_destroy_texture(destroyed_texture);
+    }
+
+    /// Creates the texture with the new size and returns the old one, which still has to be destroyed
+    pub(crate) fn replace_texture(&mut self, new_size: UVec2, device: &wgpu::Device) -> wgpu::Texture {
         let texture_descriptor = texture::create_render_texture_descriptor(
             self.format,
             new_size.x,
             new_size.y,
             Some(self.texture_name.as_str()),
         );
-        let destroyed_texture = std::mem::replace(&mut self.texture, graphics.device.create_texture(&texture_descriptor));
-        graphics.queue_destroy_texture(destroyed_texture);
+        let replaced_texture = std::mem::replace(&mut self.texture, device.create_texture(&texture_descriptor));
+        self.size = new_size;
 
         let texture_view_descriptor = self.get_texture_view_descriptor();
         self.texture_view = self.texture.create_view(&texture_view_descriptor);
+        replaced_texture
     }
 }
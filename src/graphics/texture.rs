@@ -16,6 +16,7 @@ pub fn create_render_texture_descriptor(
             depth_or_array_layers: 1,
         },
         usage: wgpu::TextureUsages::COPY_DST
+            | wgpu::TextureUsages::COPY_SRC
             | wgpu::TextureUsages::TEXTURE_BINDING
             | wgpu::TextureUsages::RENDER_ATTACHMENT,
     }
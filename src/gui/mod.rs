@@ -1 +1,2 @@
-pub mod rect_ui;
\ No newline at end of file
+pub mod rect_ui;
+pub mod window;
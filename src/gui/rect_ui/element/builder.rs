@@ -2,6 +2,7 @@ use glam::{UVec2, Vec2};
 
 use crate::{
     color::*,
+    entity_component::GlobalTransform2D,
     gui::rect_ui::{BorderRadius, ExtraBufferData, GUIRects, Rect},
 };
 
@@ -128,6 +129,18 @@ impl ElementBuilder {
         self
     }
 
+    /// Places the element relative to the transform, e.g. the global transform of its parent entity
+    pub fn set_parent_transform(mut self, parent: &GlobalTransform2D) -> Self {
+        let rect = parent.transform_rect(Rect {
+            position: self.position,
+            size: self.size,
+        });
+        self.position = rect.position;
+        self.size = rect.size;
+        self.rotation += parent.transform().rotation;
+        self
+    }
+
     pub fn set_ui_mask(mut self, value: u16) -> Self{
         self.ui_mask = value;
         self
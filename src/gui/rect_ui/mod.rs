@@ -12,7 +12,7 @@ use glam::{vec2, UVec2, Vec2};
 
 use crate::{
     math_utils::lerp_vec2,
-    graphics::{render_texture::RenderTexture, Graphics},
+    graphics::{render_texture::{RenderTexture, RenderTextureKey}, Graphics},
     slotmap::Slotmap,
 };
 
@@ -196,7 +196,7 @@ impl GUIRects {
         render_system: &Graphics,
         system_bind_group_layout: &wgpu::BindGroupLayout,
         size: UVec2,
-        render_texture_slotmap: &mut Slotmap<RenderTexture>,
+        render_texture_slotmap: &mut Slotmap<RenderTexture, RenderTextureKey>,
         initial_capacity: usize,
     ) -> Self {
         let texture_atlas = TextureAtlas::new(render_system, 1024, 1024, 2);
@@ -227,7 +227,7 @@ impl GUIRects {
         }
     }
 
-    pub fn get_color_rt<'a>(&self, rt_slotmap: &'a Slotmap<RenderTexture>) -> &'a RenderTexture {
+    pub fn get_color_rt<'a>(&self, rt_slotmap: &'a Slotmap<RenderTexture, RenderTextureKey>) -> &'a RenderTexture {
         rt_slotmap
             .get_value(&self.render_texture.color_texture_key)
             .expect("GUI Color Render Texture not found")
@@ -237,7 +237,7 @@ impl GUIRects {
         &mut self,
         new_size: UVec2,
         render_system: &Graphics,
-        render_texture_slotmap: &mut Slotmap<RenderTexture>,
+        render_texture_slotmap: &mut Slotmap<RenderTexture, RenderTextureKey>,
     ) {
         let color_rt = render_texture_slotmap
             .get_value_mut(&self.render_texture.color_texture_key)
@@ -81,8 +81,8 @@ pub struct GUIRenderPassData {
 
 impl GUIRenderPassData {
     pub fn new(graphics: &Graphics) -> Self {
-        let width = graphics.render_window.size.x;
-        let height = graphics.render_window.size.y;
+        let width = graphics.render_target.size().x;
+        let height = graphics.render_target.size().y;
         let buffer = graphics.create_buffer(
             "GUI render pass buffer",
             bytemuck::bytes_of(&[vec4(width as f32, height as f32, 0.0, 0.0)]),
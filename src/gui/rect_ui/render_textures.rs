@@ -1,5 +1,5 @@
-use crate::slotmap::{Slotmap, SlotKey};
-use crate::graphics::render_texture::RenderTexture;
+use crate::slotmap::Slotmap;
+use crate::graphics::render_texture::{RenderTexture, RenderTextureKey};
 use crate::graphics::Graphics;
 use glam::{uvec2, vec2};
 
@@ -19,8 +19,8 @@ pub fn get_color_target_states() -> [Option<wgpu::ColorTargetState>; 2] {
 }
 
 pub struct GUIRenderTexture {
-    pub color_texture_key: SlotKey,
-    pub mask_texture_key: SlotKey,
+    pub color_texture_key: RenderTextureKey,
+    pub mask_texture_key: RenderTextureKey,
 }
 
 impl GUIRenderTexture {
@@ -28,7 +28,7 @@ impl GUIRenderTexture {
         render_system: &Graphics,
         width: u32,
         height: u32,
-        render_texture_slotmap: &mut Slotmap<RenderTexture>,
+        render_texture_slotmap: &mut Slotmap<RenderTexture, RenderTextureKey>,
     ) -> Self {
         let color_texture = RenderTexture::create_and_store(
             wgpu::TextureFormat::Rgba8Unorm,
@@ -0,0 +1,1020 @@
+use glam::{vec2, UVec2, Vec2};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    color::RGBA,
+    entity_component::public_data::PublicDataSlotmap,
+    gui::rect_ui::{element::builder::ElementBuilder, event::UIEvent, BorderRadius, GUIRects, Rect},
+};
+
+use super::{
+    draw_text, FloatingWindowManager, Window, WindowContext, WindowFont, WindowInput, WindowKey,
+    WindowStyle,
+};
+
+/// How a split divides its rect
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SplitDirection {
+    /// The first node is on the left and the second one on the right
+    Horizontal,
+    /// The first node is on top and the second one below
+    Vertical,
+}
+
+/// Where a window is docked relative to a tab group
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DockPosition {
+    /// The window is added as a new tab
+    Center,
+    Left,
+    Right,
+    Top,
+    Bottom,
+}
+
+/// Address of a node of the dock tree, each index selects the first (0) or second (1) node of a split
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct DockPath(Vec<usize>);
+
+impl DockPath {
+    pub fn root() -> Self {
+        Self::default()
+    }
+
+    fn child(&self, index: usize) -> Self {
+        let mut path = self.0.clone();
+        path.push(index);
+        Self(path)
+    }
+
+    /// Path of the same node after the node at `split` was moved into a new split as its child `index`
+    fn after_split(&self, split: &DockPath, index: usize) -> Self {
+        let mut path = self.0.clone();
+        if path.starts_with(&split.0) {
+            path.insert(split.0.len(), index);
+        }
+        Self(path)
+    }
+
+    /// Path of the same node after the tab group at `removed` was removed and its split replaced by the other node.\
+    /// Returns `None` for the removed group and its split
+    fn after_collapse(&self, removed: &DockPath) -> Option<Self> {
+        let (_, parent) = removed.0.split_last()?;
+        if self.0.starts_with(&removed.0) || self.0 == parent {
+            return None;
+        }
+        let mut path = self.0.clone();
+        //The nodes of the other child move up to the place of the split
+        if path.starts_with(parent) {
+            path.remove(parent.len());
+        }
+        Some(Self(path))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DockTarget {
+    /// Path of a tab group, any path can be used if nothing is docked yet
+    pub path: DockPath,
+    pub position: DockPosition,
+}
+
+enum DockNode {
+    Split {
+        direction: SplitDirection,
+        /// Part of the rect used by the first node
+        ratio: f32,
+        first: Box<DockNode>,
+        second: Box<DockNode>,
+    },
+    Tabs {
+        windows: Vec<Box<dyn Window>>,
+        selected: usize,
+    },
+}
+
+impl DockNode {
+    fn tabs(window: Box<dyn Window>) -> Self {
+        DockNode::Tabs {
+            windows: vec![window],
+            selected: 0,
+        }
+    }
+
+    fn node(&self, path: &[usize]) -> Option<&DockNode> {
+        match (path.split_first(), self) {
+            (None, node) => Some(node),
+            (Some((index, rest)), DockNode::Split { first, second, .. }) => match index {
+                0 => first.node(rest),
+                1 => second.node(rest),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn node_mut(&mut self, path: &[usize]) -> Option<&mut DockNode> {
+        match (path.split_first(), self) {
+            (None, node) => Some(node),
+            (Some((index, rest)), DockNode::Split { first, second, .. }) => match index {
+                0 => first.node_mut(rest),
+                1 => second.node_mut(rest),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn layout(&self, path: DockPath, rect: Rect, splitter_size: f32, items: &mut Vec<LayoutItem>) {
+        match self {
+            DockNode::Split {
+                direction,
+                ratio,
+                first,
+                second,
+            } => {
+                let (first_rect, second_rect, splitter_rect) =
+                    split_rect(rect, *direction, *ratio, splitter_size);
+                items.push(LayoutItem::Splitter {
+                    path: path.clone(),
+                    rect: splitter_rect,
+                    direction: *direction,
+                    parent_rect: rect,
+                });
+                first.layout(path.child(0), first_rect, splitter_size, items);
+                second.layout(path.child(1), second_rect, splitter_size, items);
+            }
+            DockNode::Tabs { .. } => items.push(LayoutItem::Tabs { path, rect }),
+        }
+    }
+
+    fn into_windows(self, windows: &mut Vec<Box<dyn Window>>) {
+        match self {
+            DockNode::Split { first, second, .. } => {
+                first.into_windows(windows);
+                second.into_windows(windows);
+            }
+            DockNode::Tabs { windows: tabs, .. } => windows.extend(tabs),
+        }
+    }
+
+    fn to_layout(&self) -> DockLayoutNode {
+        match self {
+            DockNode::Split {
+                direction,
+                ratio,
+                first,
+                second,
+            } => DockLayoutNode::Split {
+                direction: *direction,
+                ratio: *ratio,
+                first: Box::new(first.to_layout()),
+                second: Box::new(second.to_layout()),
+            },
+            DockNode::Tabs { windows, selected } => DockLayoutNode::Tabs {
+                windows: windows
+                    .iter()
+                    .map(|window| window.get_name().to_string())
+                    .collect(),
+                selected: *selected,
+            },
+        }
+    }
+}
+
+/// Splits the rect leaving space for the splitter between the two parts
+fn split_rect(rect: Rect, direction: SplitDirection, ratio: f32, splitter_size: f32) -> (Rect, Rect, Rect) {
+    let bounds_min = rect.bottom_left_position();
+    match direction {
+        SplitDirection::Horizontal => {
+            let split_x = bounds_min.x + rect.size.x * ratio;
+            let first_width = (split_x - bounds_min.x - splitter_size * 0.5).max(0.0);
+            let second_width = (rect.size.x - first_width - splitter_size).max(0.0);
+            let first = Rect {
+                position: vec2(bounds_min.x + first_width * 0.5, rect.position.y),
+                size: vec2(first_width, rect.size.y),
+            };
+            let second = Rect {
+                position: vec2(bounds_min.x + rect.size.x - second_width * 0.5, rect.position.y),
+                size: vec2(second_width, rect.size.y),
+            };
+            let splitter = Rect {
+                position: vec2(split_x, rect.position.y),
+                size: vec2(splitter_size, rect.size.y),
+            };
+            (first, second, splitter)
+        }
+        SplitDirection::Vertical => {
+            let top = bounds_min.y + rect.size.y;
+            let split_y = top - rect.size.y * ratio;
+            let first_height = (top - split_y - splitter_size * 0.5).max(0.0);
+            let second_height = (rect.size.y - first_height - splitter_size).max(0.0);
+            let first = Rect {
+                position: vec2(rect.position.x, top - first_height * 0.5),
+                size: vec2(rect.size.x, first_height),
+            };
+            let second = Rect {
+                position: vec2(rect.position.x, bounds_min.y + second_height * 0.5),
+                size: vec2(rect.size.x, second_height),
+            };
+            let splitter = Rect {
+                position: vec2(rect.position.x, split_y),
+                size: vec2(rect.size.x, splitter_size),
+            };
+            (first, second, splitter)
+        }
+    }
+}
+
+/// Rect of a node of the dock tree, computed from the dock area every time it is needed
+#[derive(Debug, Clone)]
+enum LayoutItem {
+    Splitter {
+        path: DockPath,
+        rect: Rect,
+        direction: SplitDirection,
+        parent_rect: Rect,
+    },
+    Tabs {
+        path: DockPath,
+        rect: Rect,
+    },
+}
+
+/// Serializable description of a dock tree, the windows are stored by their name
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum DockLayoutNode {
+    Split {
+        direction: SplitDirection,
+        ratio: f32,
+        first: Box<DockLayoutNode>,
+        second: Box<DockLayoutNode>,
+    },
+    Tabs {
+        windows: Vec<String>,
+        selected: usize,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FloatingWindowLayout {
+    pub name: String,
+    pub position: Vec2,
+    pub size: Vec2,
+}
+
+/// Workspace that can be saved as JSON and restored with `DockingManager::restore_layout`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+pub struct DockLayout {
+    pub root: Option<DockLayoutNode>,
+    /// From back to front
+    pub floating: Vec<FloatingWindowLayout>,
+}
+
+impl DockLayout {
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+}
+
+/// Sizes are in pixels
+#[derive(Debug, Clone, Copy)]
+pub struct DockStyle {
+    pub splitter_size: f32,
+    pub tab_bar_height: f32,
+    pub tab_width: f32,
+    pub tab_padding: f32,
+    pub tab_radius: f32,
+    /// Distance the cursor has to move out of the tab bar to undock a tab
+    pub undock_distance: f32,
+    pub dock_target_size: f32,
+    /// Smallest ratio of a split when a splitter is dragged
+    pub min_split_ratio: f32,
+    pub background_color: RGBA,
+    pub tab_bar_color: RGBA,
+    pub tab_color: RGBA,
+    pub selected_tab_color: RGBA,
+    pub tab_text_color: RGBA,
+    pub splitter_color: RGBA,
+    pub dock_target_color: RGBA,
+    pub dock_preview_color: RGBA,
+}
+
+impl Default for DockStyle {
+    fn default() -> Self {
+        Self {
+            splitter_size: 4.0,
+            tab_bar_height: 24.0,
+            tab_width: 120.0,
+            tab_padding: 8.0,
+            tab_radius: 4.0,
+            undock_distance: 16.0,
+            dock_target_size: 32.0,
+            min_split_ratio: 0.1,
+            background_color: RGBA::rgb(0.12, 0.12, 0.14),
+            tab_bar_color: RGBA::rgb(0.08, 0.08, 0.1),
+            tab_color: RGBA::rgb(0.2, 0.2, 0.23),
+            selected_tab_color: RGBA::rgb(0.25, 0.32, 0.5),
+            tab_text_color: RGBA::WHITE,
+            splitter_color: RGBA::rgb(0.05, 0.05, 0.06),
+            dock_target_color: RGBA::rgb(0.3, 0.45, 0.8),
+            dock_preview_color: RGBA::rgb(0.3, 0.45, 0.8).set_alpha(0.25),
+        }
+    }
+}
+
+impl DockStyle {
+    fn tab_bar_rect(&self, rect: Rect) -> Rect {
+        let top = rect.position.y + rect.size.y * 0.5;
+        Rect {
+            position: vec2(rect.position.x, top - self.tab_bar_height * 0.5),
+            size: vec2(rect.size.x, self.tab_bar_height),
+        }
+    }
+
+    fn content_rect(&self, rect: Rect) -> Rect {
+        Rect {
+            position: rect.position - vec2(0.0, self.tab_bar_height * 0.5),
+            size: (rect.size - vec2(0.0, self.tab_bar_height)).max(Vec2::ZERO),
+        }
+    }
+
+    /// The tabs get narrower when they do not fit in the tab bar
+    fn tab_rect(&self, rect: Rect, tab_count: usize, index: usize) -> Rect {
+        let tab_bar = self.tab_bar_rect(rect);
+        let tab_width = self.tab_width.min(tab_bar.size.x / tab_count.max(1) as f32);
+        let left = tab_bar.position.x - tab_bar.size.x * 0.5;
+        Rect {
+            position: vec2(left + tab_width * (index as f32 + 0.5), tab_bar.position.y),
+            size: vec2(tab_width, self.tab_bar_height),
+        }
+    }
+
+    /// Cross of targets in the middle of the tab group
+    fn dock_target_rects(&self, rect: Rect) -> [(DockPosition, Rect); 5] {
+        let size = Vec2::splat(self.dock_target_size);
+        let distance = self.dock_target_size * 1.25;
+        let target = |position: DockPosition, offset: Vec2| {
+            (
+                position,
+                Rect {
+                    position: rect.position + offset,
+                    size,
+                },
+            )
+        };
+        [
+            target(DockPosition::Center, Vec2::ZERO),
+            target(DockPosition::Left, vec2(-distance, 0.0)),
+            target(DockPosition::Right, vec2(distance, 0.0)),
+            target(DockPosition::Top, vec2(0.0, distance)),
+            target(DockPosition::Bottom, vec2(0.0, -distance)),
+        ]
+    }
+}
+
+/// Part of the tab group rect the docked window would use
+fn dock_preview_rect(rect: Rect, position: DockPosition) -> Rect {
+    let half_size = rect.size * 0.5;
+    let (offset, size) = match position {
+        DockPosition::Center => (Vec2::ZERO, rect.size),
+        DockPosition::Left => (vec2(-half_size.x * 0.5, 0.0), vec2(half_size.x, rect.size.y)),
+        DockPosition::Right => (vec2(half_size.x * 0.5, 0.0), vec2(half_size.x, rect.size.y)),
+        DockPosition::Top => (vec2(0.0, half_size.y * 0.5), vec2(rect.size.x, half_size.y)),
+        DockPosition::Bottom => (vec2(0.0, -half_size.y * 0.5), vec2(rect.size.x, half_size.y)),
+    };
+    Rect {
+        position: rect.position + offset,
+        size,
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Interaction {
+    None,
+    Splitter {
+        path: DockPath,
+        direction: SplitDirection,
+        parent_rect: Rect,
+    },
+    Tab {
+        path: DockPath,
+        index: usize,
+        press_position: Vec2,
+    },
+}
+
+/// Layout system that splits the screen into tab groups separated by splitters.\
+/// Dragging a tab out of its tab bar undocks it into a floating window, and dropping a floating window on the
+/// targets shown over a tab group docks it again
+pub struct DockingManager {
+    root: Option<DockNode>,
+    area: Rect,
+    focused_group: Option<DockPath>,
+    interaction: Interaction,
+    input: WindowInput,
+    /// The windows that are not docked, they are drawn over the docked windows
+    pub floating: FloatingWindowManager,
+    pub style: DockStyle,
+}
+
+impl DockingManager {
+    pub fn new(screen_size: UVec2, style: DockStyle, window_style: WindowStyle) -> Self {
+        let size = screen_size.as_vec2();
+        Self {
+            root: None,
+            area: Rect {
+                position: size * 0.5,
+                size,
+            },
+            focused_group: None,
+            interaction: Interaction::None,
+            input: WindowInput::default(),
+            floating: FloatingWindowManager::new(screen_size, window_style),
+            style,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    /// Returns the window if the target is not a tab group
+    pub fn dock(&mut self, window: Box<dyn Window>, target: &DockTarget) -> Result<(), Box<dyn Window>> {
+        let Some(root) = self.root.as_mut() else {
+            self.root = Some(DockNode::tabs(window));
+            return Ok(());
+        };
+        let Some(node) = root.node_mut(&target.path.0) else {
+            return Err(window);
+        };
+        let DockNode::Tabs { windows, selected } = node else {
+            return Err(window);
+        };
+
+        let (direction, new_first) = match target.position {
+            DockPosition::Center => {
+                windows.push(window);
+                *selected = windows.len() - 1;
+                return Ok(());
+            }
+            DockPosition::Left => (SplitDirection::Horizontal, true),
+            DockPosition::Right => (SplitDirection::Horizontal, false),
+            DockPosition::Top => (SplitDirection::Vertical, true),
+            DockPosition::Bottom => (SplitDirection::Vertical, false),
+        };
+        let old_node = Box::new(std::mem::replace(
+            node,
+            DockNode::Tabs {
+                windows: Vec::new(),
+                selected: 0,
+            },
+        ));
+        let new_node = Box::new(DockNode::tabs(window));
+        let (first, second) = if new_first {
+            (new_node, old_node)
+        } else {
+            (old_node, new_node)
+        };
+        *node = DockNode::Split {
+            direction,
+            ratio: 0.5,
+            first,
+            second,
+        };
+        let old_index = if new_first { 1 } else { 0 };
+        self.remap_paths(|path| Some(path.after_split(&target.path, old_index)));
+        Ok(())
+    }
+
+    /// Removes the tab, the tab groups without tabs are removed and their split is replaced by the other node
+    pub fn undock(&mut self, path: &DockPath, index: usize) -> Option<Box<dyn Window>> {
+        let DockNode::Tabs { windows, selected } = self.root.as_mut()?.node_mut(&path.0)? else {
+            return None;
+        };
+        if index >= windows.len() {
+            return None;
+        }
+        let window = windows.remove(index);
+        *selected = (*selected).min(windows.len().saturating_sub(1));
+        //The index of the pressed tab may have changed
+        if matches!(&self.interaction, Interaction::Tab { path: tab_path, .. } if tab_path == path) {
+            self.interaction = Interaction::None;
+        }
+        if windows.is_empty() {
+            self.remove_empty_group(path);
+        }
+        Some(window)
+    }
+
+    /// Undocks the tab into a floating window centered on the position, with the size it had while docked
+    pub fn undock_to_floating(&mut self, path: &DockPath, index: usize, position: Vec2) -> Option<WindowKey> {
+        let content_size = self
+            .tab_group_rects()
+            .into_iter()
+            .find(|(group_path, _)| group_path == path)
+            .map(|(_, rect)| self.style.content_rect(rect).size)?;
+        let window = self.undock(path, index)?;
+        let size = self.floating.style.window_size(content_size);
+        Some(self.floating.add_window_with_rect(window, Rect { position, size }))
+    }
+
+    fn remove_empty_group(&mut self, path: &DockPath) {
+        self.remap_paths(|other| other.after_collapse(path));
+        let Some((last, parent_path)) = path.0.split_last() else {
+            self.root = None;
+            return;
+        };
+        let Some(parent) = self.root.as_mut().and_then(|root| root.node_mut(parent_path)) else {
+            return;
+        };
+        let placeholder = DockNode::Tabs {
+            windows: Vec::new(),
+            selected: 0,
+        };
+        if let DockNode::Split { first, second, .. } = std::mem::replace(parent, placeholder) {
+            *parent = if *last == 0 { *second } else { *first };
+        }
+    }
+
+    /// Updates the paths kept between events after the tree was restructured, the ones of removed nodes are cleared.\
+    /// A splitter that is being dragged is released if its split moved, because the rect it was pressed in changed
+    fn remap_paths(&mut self, remap: impl Fn(&DockPath) -> Option<DockPath>) {
+        self.focused_group = self.focused_group.as_ref().and_then(&remap);
+        let keep_interaction = match &mut self.interaction {
+            Interaction::None => true,
+            Interaction::Splitter { path, .. } => remap(path).as_ref() == Some(path),
+            Interaction::Tab { path, .. } => match remap(path) {
+                Some(new_path) => {
+                    *path = new_path;
+                    true
+                }
+                None => false,
+            },
+        };
+        if !keep_interaction {
+            self.interaction = Interaction::None;
+        }
+    }
+
+    /// Tab group that was clicked last, `None` if the last click was not on a tab group
+    pub fn focused_group(&self) -> Option<&DockPath> {
+        self.focused_group.as_ref()
+    }
+
+    /// The tab groups with their rects, including the tab bar
+    pub fn tab_group_rects(&self) -> Vec<(DockPath, Rect)> {
+        self.layout_items()
+            .into_iter()
+            .filter_map(|item| match item {
+                LayoutItem::Tabs { path, rect } => Some((path, rect)),
+                LayoutItem::Splitter { .. } => None,
+            })
+            .collect()
+    }
+
+    /// The names of the windows of the tab group and the selected tab
+    pub fn tab_names(&self, path: &DockPath) -> Option<(Vec<&str>, usize)> {
+        match self.root.as_ref()?.node(&path.0)? {
+            DockNode::Tabs { windows, selected } => Some((
+                windows.iter().map(|window| window.get_name()).collect(),
+                *selected,
+            )),
+            DockNode::Split { .. } => None,
+        }
+    }
+
+    /// Finds the first docked window with the name
+    pub fn find_window(&self, name: &str) -> Option<(DockPath, usize)> {
+        self.tab_group_rects().into_iter().find_map(|(path, _)| {
+            let (names, _) = self.tab_names(&path)?;
+            let index = names.iter().position(|window_name| *window_name == name)?;
+            Some((path, index))
+        })
+    }
+
+    fn layout_items(&self) -> Vec<LayoutItem> {
+        let mut items = Vec::new();
+        if let Some(root) = &self.root {
+            root.layout(DockPath::root(), self.area, self.style.splitter_size, &mut items);
+        }
+        items
+    }
+
+    /// The target under the position, only while a floating window is being moved
+    pub fn dock_target_at(&self, position: Vec2) -> Option<DockTarget> {
+        self.floating.moving_window()?;
+        self.dock_targets()
+            .into_iter()
+            .find(|(_, rect, _)| rect.inside_rect(position))
+            .map(|(target, _, _)| target)
+    }
+
+    /// The targets of the tab group under the cursor, with their rect and the rect of their preview
+    fn dock_targets(&self) -> Vec<(DockTarget, Rect, Rect)> {
+        let cursor_position = self.input.cursor_position;
+        let Some((path, rect)) = self
+            .tab_group_rects()
+            .into_iter()
+            .find(|(_, rect)| rect.inside_rect(cursor_position))
+        else {
+            if self.root.is_none() {
+                let target = DockTarget {
+                    path: DockPath::root(),
+                    position: DockPosition::Center,
+                };
+                let target_rect = self.style.dock_target_rects(self.area)[0].1;
+                return vec![(target, target_rect, self.area)];
+            }
+            return Vec::new();
+        };
+        self.style
+            .dock_target_rects(rect)
+            .into_iter()
+            .map(|(position, target_rect)| {
+                (
+                    DockTarget {
+                        path: path.clone(),
+                        position,
+                    },
+                    target_rect,
+                    dock_preview_rect(rect, position),
+                )
+            })
+            .collect()
+    }
+
+    /// Handles the events of the floating windows first, because they are drawn over the docked windows.\
+    /// The mouse button events used by the layout are consumed
+    pub fn handle_event(&mut self, event: &mut UIEvent) {
+        self.input.handle_event(event);
+        if let UIEvent::Resize(size) = event {
+            let size = size.as_vec2();
+            self.area = Rect {
+                position: size * 0.5,
+                size,
+            };
+        }
+
+        //A floating window dropped on a target is docked
+        if let UIEvent::MouseButton(mouse_input) = event {
+            if mouse_input.is_left_released() {
+                if let (Some(key), Some(target)) = (
+                    self.floating.moving_window(),
+                    self.dock_target_at(self.input.cursor_position),
+                ) {
+                    if let Some(window) = self.floating.remove_window(key) {
+                        if let Err(window) = self.dock(window, &target) {
+                            self.floating.add_window(window, self.input.cursor_position);
+                        }
+                    }
+                    event.consume();
+                    return;
+                }
+            }
+        }
+
+        self.floating.handle_event(event);
+        let consumed = match event {
+            UIEvent::MouseMove { .. } => self.drag(),
+            UIEvent::MouseButton(mouse_input) if mouse_input.is_left_pressed() => self.press(),
+            UIEvent::MouseButton(mouse_input) if mouse_input.is_left_released() => {
+                let was_interacting = !matches!(self.interaction, Interaction::None);
+                self.interaction = Interaction::None;
+                was_interacting
+            }
+            _ => false,
+        };
+        if consumed {
+            event.consume();
+        }
+    }
+
+    fn press(&mut self) -> bool {
+        let cursor_position = self.input.cursor_position;
+        self.focused_group = None;
+        for item in self.layout_items() {
+            match item {
+                LayoutItem::Splitter {
+                    path,
+                    rect,
+                    direction,
+                    parent_rect,
+                } if rect.inside_rect(cursor_position) => {
+                    self.interaction = Interaction::Splitter {
+                        path,
+                        direction,
+                        parent_rect,
+                    };
+                    return true;
+                }
+                LayoutItem::Tabs { path, rect } if rect.inside_rect(cursor_position) => {
+                    self.focused_group = Some(path.clone());
+                    if !self.style.tab_bar_rect(rect).inside_rect(cursor_position) {
+                        return false;
+                    }
+                    let Some((names, _)) = self.tab_names(&path) else {
+                        return false;
+                    };
+                    let tab_index = (0..names.len()).find(|index| {
+                        self.style
+                            .tab_rect(rect, names.len(), *index)
+                            .inside_rect(cursor_position)
+                    });
+                    if let Some(index) = tab_index {
+                        self.select_tab(&path, index);
+                        self.interaction = Interaction::Tab {
+                            path,
+                            index,
+                            press_position: cursor_position,
+                        };
+                    }
+                    return true;
+                }
+                _ => {}
+            }
+        }
+        false
+    }
+
+    pub fn select_tab(&mut self, path: &DockPath, index: usize) -> bool {
+        match self.root.as_mut().and_then(|root| root.node_mut(&path.0)) {
+            Some(DockNode::Tabs { windows, selected }) if index < windows.len() => {
+                *selected = index;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn drag(&mut self) -> bool {
+        let cursor_position = self.input.cursor_position;
+        match self.interaction.clone() {
+            Interaction::None => false,
+            Interaction::Splitter {
+                path,
+                direction,
+                parent_rect,
+            } => {
+                let bounds_min = parent_rect.bottom_left_position();
+                let ratio = match direction {
+                    SplitDirection::Horizontal => (cursor_position.x - bounds_min.x) / parent_rect.size.x,
+                    SplitDirection::Vertical => {
+                        (bounds_min.y + parent_rect.size.y - cursor_position.y) / parent_rect.size.y
+                    }
+                };
+                let min_ratio = self.style.min_split_ratio;
+                if let Some(DockNode::Split { ratio: split_ratio, .. }) =
+                    self.root.as_mut().and_then(|root| root.node_mut(&path.0))
+                {
+                    *split_ratio = ratio.clamp(min_ratio, 1.0 - min_ratio);
+                }
+                true
+            }
+            Interaction::Tab {
+                path,
+                index,
+                press_position,
+            } => {
+                let Some((_, rect)) = self
+                    .tab_group_rects()
+                    .into_iter()
+                    .find(|(group_path, _)| *group_path == path)
+                else {
+                    return false;
+                };
+                let outside_tab_bar = !self.style.tab_bar_rect(rect).inside_rect(cursor_position);
+                let moved_enough =
+                    (cursor_position - press_position).length() > self.style.undock_distance;
+                if outside_tab_bar && moved_enough {
+                    self.interaction = Interaction::None;
+                    //The title bar of the new floating window is placed under the cursor
+                    let title_bar_height = self.floating.style.title_bar_height;
+                    let content_size = self.style.content_rect(rect).size;
+                    let window_height = self.floating.style.window_size(content_size).y;
+                    let grab_offset = vec2(0.0, title_bar_height * 0.5 - window_height * 0.5);
+                    if let Some(key) =
+                        self.undock_to_floating(&path, index, cursor_position + grab_offset)
+                    {
+                        self.floating.start_moving(key, grab_offset);
+                    }
+                }
+                true
+            }
+        }
+    }
+
+    /// Draws the tab groups and updates the windows of the selected tabs, then the floating windows.\
+    /// The dock targets are drawn on top while a floating window is being moved
+    pub fn update(
+        &mut self,
+        gui_rects: &mut GUIRects,
+        font: Option<WindowFont>,
+        public_data: &mut PublicDataSlotmap,
+    ) {
+        let cursor_position = self.input.cursor_position;
+        let cursor_over_floating = self.floating.window_at(cursor_position).is_some();
+        let floating_focused = self.floating.focused().is_some();
+        for item in self.layout_items() {
+            match item {
+                LayoutItem::Splitter { rect, .. } => {
+                    ElementBuilder::new_with_rect(rect)
+                        .set_color(self.style.splitter_color.into())
+                        .build(gui_rects);
+                }
+                LayoutItem::Tabs { path, rect } => {
+                    let focused = !floating_focused && self.focused_group.as_ref() == Some(&path);
+                    let Some(DockNode::Tabs { windows, selected }) =
+                        self.root.as_mut().and_then(|root| root.node_mut(&path.0))
+                    else {
+                        continue;
+                    };
+                    draw_tab_group(gui_rects, &self.style, rect, windows, *selected, font.as_ref());
+
+                    let content_rect = self.style.content_rect(rect);
+                    let mut context = WindowContext {
+                        rect: content_rect,
+                        input: &self.input,
+                        hovered: !cursor_over_floating && content_rect.inside_rect(cursor_position),
+                        focused,
+                        gui_rects,
+                        font,
+                        public_data,
+                    };
+                    windows[*selected].update(&mut context);
+                }
+            }
+        }
+
+        self.floating.update(gui_rects, font, public_data);
+        if self.floating.moving_window().is_some() {
+            for (_, target_rect, preview_rect) in self.dock_targets() {
+                if target_rect.inside_rect(cursor_position) {
+                    ElementBuilder::new_with_rect(preview_rect)
+                        .set_color(self.style.dock_preview_color.into())
+                        .build(gui_rects);
+                }
+                ElementBuilder::new_with_rect(target_rect)
+                    .set_round_rect(BorderRadius::ForAll(self.style.tab_radius).into())
+                    .set_color(self.style.dock_target_color.into())
+                    .build(gui_rects);
+            }
+        }
+        self.input.clear_frame_input();
+    }
+
+    pub fn layout(&self) -> DockLayout {
+        let floating = self
+            .floating
+            .z_order()
+            .iter()
+            .filter_map(|key| {
+                Some(FloatingWindowLayout {
+                    name: self.floating.window(*key)?.get_name().to_string(),
+                    position: self.floating.window_rect(*key)?.position,
+                    size: self.floating.window_rect(*key)?.size,
+                })
+            })
+            .collect();
+        DockLayout {
+            root: self.root.as_ref().map(DockNode::to_layout),
+            floating,
+        }
+    }
+
+    /// Rebuilds the docked and floating windows from the layout, matching the windows by their name.\
+    /// The windows of the manager are reused together with the given ones, the windows that are not in the
+    /// layout are returned
+    pub fn restore_layout(
+        &mut self,
+        layout: &DockLayout,
+        windows: Vec<Box<dyn Window>>,
+    ) -> Vec<Box<dyn Window>> {
+        let mut available = Vec::new();
+        if let Some(root) = self.root.take() {
+            root.into_windows(&mut available);
+        }
+        for key in self.floating.z_order().to_vec() {
+            available.extend(self.floating.remove_window(key));
+        }
+        available.extend(windows);
+        let mut available: Vec<Option<Box<dyn Window>>> = available.into_iter().map(Some).collect();
+
+        self.focused_group = None;
+        self.interaction = Interaction::None;
+        self.root = layout
+            .root
+            .as_ref()
+            .and_then(|node| build_node(node, &mut available, self.style.min_split_ratio));
+        for floating in &layout.floating {
+            if let Some(window) = take_window(&mut available, &floating.name) {
+                let rect = Rect {
+                    position: floating.position,
+                    size: floating.size,
+                };
+                self.floating.add_window_with_rect(window, rect);
+            }
+        }
+        available.into_iter().flatten().collect()
+    }
+}
+
+fn take_window(available: &mut [Option<Box<dyn Window>>], name: &str) -> Option<Box<dyn Window>> {
+    available
+        .iter_mut()
+        .find(|window| window.as_ref().is_some_and(|window| window.get_name() == name))?
+        .take()
+}
+
+/// The tab groups without any of their windows are left out.\
+/// The ratios are clamped like the ones set by dragging a splitter
+fn build_node(
+    node: &DockLayoutNode,
+    available: &mut [Option<Box<dyn Window>>],
+    min_ratio: f32,
+) -> Option<DockNode> {
+    match node {
+        DockLayoutNode::Split {
+            direction,
+            ratio,
+            first,
+            second,
+        } => match (
+            build_node(first, available, min_ratio),
+            build_node(second, available, min_ratio),
+        ) {
+            (Some(first), Some(second)) => Some(DockNode::Split {
+                direction: *direction,
+                ratio: if ratio.is_nan() {
+                    0.5
+                } else {
+                    ratio.clamp(min_ratio, 1.0 - min_ratio)
+                },
+                first: Box::new(first),
+                second: Box::new(second),
+            }),
+            (Some(node), None) | (None, Some(node)) => Some(node),
+            (None, None) => None,
+        },
+        DockLayoutNode::Tabs { windows, selected } => {
+            let windows: Vec<Box<dyn Window>> = windows
+                .iter()
+                .filter_map(|name| take_window(available, name))
+                .collect();
+            if windows.is_empty() {
+                return None;
+            }
+            let selected = (*selected).min(windows.len() - 1);
+            Some(DockNode::Tabs { windows, selected })
+        }
+    }
+}
+
+fn draw_tab_group(
+    gui_rects: &mut GUIRects,
+    style: &DockStyle,
+    rect: Rect,
+    windows: &[Box<dyn Window>],
+    selected: usize,
+    font: Option<&WindowFont>,
+) {
+    ElementBuilder::new_with_rect(rect)
+        .set_color(style.background_color.into())
+        .build(gui_rects);
+    ElementBuilder::new_with_rect(style.tab_bar_rect(rect))
+        .set_color(style.tab_bar_color.into())
+        .build(gui_rects);
+
+    for (index, window) in windows.iter().enumerate() {
+        let tab_rect = style.tab_rect(rect, windows.len(), index);
+        let tab_color = if index == selected {
+            style.selected_tab_color
+        } else {
+            style.tab_color
+        };
+        ElementBuilder::new_with_rect(tab_rect.offset_size(vec2(-2.0, 0.0)))
+            .set_round_rect(
+                BorderRadius::ForTopBottom {
+                    top: style.tab_radius,
+                    bottom: 0.0,
+                }
+                .into(),
+            )
+            .set_color(tab_color.into())
+            .build(gui_rects);
+        if let Some(font) = font {
+            draw_text(
+                gui_rects,
+                font,
+                window.get_name(),
+                tab_rect.left_position() + vec2(style.tab_padding, 0.0),
+                style.tab_text_color,
+                tab_rect,
+            );
+        }
+    }
+}
@@ -0,0 +1,501 @@
+use glam::{vec2, UVec2, Vec2};
+
+use crate::{
+    color::RGBA,
+    entity_component::public_data::PublicDataSlotmap,
+    gui::rect_ui::{
+        element::{builder::ElementBuilder, Border},
+        event::UIEvent,
+        BorderRadius, ExtraBufferData, GUIRects, Rect,
+    },
+    slotmap::{create_custom_key, GrowthPolicy, Slotmap},
+};
+
+use super::{draw_text, Window, WindowContext, WindowFont, WindowInput};
+
+create_custom_key!(
+    /// Key of a window in a `FloatingWindowManager`
+    WindowKey;
+);
+
+/// Sizes are in pixels
+#[derive(Debug, Clone, Copy)]
+pub struct WindowStyle {
+    pub title_bar_height: f32,
+    pub title_padding: f32,
+    pub resize_handle_size: f32,
+    pub border_radius: f32,
+    pub border_size: u32,
+    /// Smallest size of a window when it is resized by the user, including the title bar
+    pub min_size: Vec2,
+    pub background_color: RGBA,
+    pub border_color: RGBA,
+    pub title_bar_color: RGBA,
+    pub focused_title_bar_color: RGBA,
+    pub title_color: RGBA,
+    pub close_button_color: RGBA,
+    pub resize_handle_color: RGBA,
+}
+
+impl Default for WindowStyle {
+    fn default() -> Self {
+        Self {
+            title_bar_height: 24.0,
+            title_padding: 8.0,
+            resize_handle_size: 12.0,
+            border_radius: 6.0,
+            border_size: 1,
+            min_size: vec2(120.0, 60.0),
+            background_color: RGBA::rgb(0.12, 0.12, 0.14),
+            border_color: RGBA::rgb(0.3, 0.3, 0.34),
+            title_bar_color: RGBA::rgb(0.2, 0.2, 0.23),
+            focused_title_bar_color: RGBA::rgb(0.25, 0.32, 0.5),
+            title_color: RGBA::WHITE,
+            close_button_color: RGBA::rgb(0.8, 0.3, 0.3),
+            resize_handle_color: RGBA::rgb(0.4, 0.4, 0.45),
+        }
+    }
+}
+
+impl WindowStyle {
+    pub fn title_bar_rect(&self, window_rect: Rect) -> Rect {
+        let top = window_rect.position.y + window_rect.size.y * 0.5;
+        Rect {
+            position: vec2(window_rect.position.x, top - self.title_bar_height * 0.5),
+            size: vec2(window_rect.size.x, self.title_bar_height),
+        }
+    }
+
+    /// Rect the window content can use, below the title bar
+    pub fn content_rect(&self, window_rect: Rect) -> Rect {
+        Rect {
+            position: window_rect.position - vec2(0.0, self.title_bar_height * 0.5),
+            size: (window_rect.size - vec2(0.0, self.title_bar_height)).max(Vec2::ZERO),
+        }
+    }
+
+    pub fn close_button_rect(&self, window_rect: Rect) -> Rect {
+        let title_bar = self.title_bar_rect(window_rect);
+        let right = title_bar.position.x + title_bar.size.x * 0.5;
+        Rect {
+            position: vec2(right - self.title_bar_height * 0.5, title_bar.position.y),
+            size: Vec2::splat(self.title_bar_height * 0.6),
+        }
+    }
+
+    /// Square on the bottom right corner of the window
+    pub fn resize_handle_rect(&self, window_rect: Rect) -> Rect {
+        let half_handle = self.resize_handle_size * 0.5;
+        let corner = window_rect.position + vec2(window_rect.size.x, -window_rect.size.y) * 0.5;
+        Rect {
+            position: corner + vec2(-half_handle, half_handle),
+            size: Vec2::splat(self.resize_handle_size),
+        }
+    }
+
+    /// Size of the window that gives the requested size to its content
+    pub fn window_size(&self, content_size: Vec2) -> Vec2 {
+        content_size + vec2(0.0, self.title_bar_height)
+    }
+}
+
+struct FloatingWindow {
+    window: Box<dyn Window>,
+    /// Includes the title bar
+    rect: Rect,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Interaction {
+    None,
+    Moving { key: WindowKey, grab_offset: Vec2 },
+    Resizing { key: WindowKey, grab_offset: Vec2 },
+}
+
+/// Layout system where every window has its own rect with a title bar, it can be moved by dragging the title bar,
+/// resized from the bottom right corner and closed with the button of the title bar.\
+/// The windows are drawn from back to front, and clicking a window brings it to the front and focuses it
+pub struct FloatingWindowManager {
+    windows: Slotmap<FloatingWindow, WindowKey>,
+    /// From back to front
+    z_order: Vec<WindowKey>,
+    focused: Option<WindowKey>,
+    interaction: Interaction,
+    input: WindowInput,
+    closed_windows: Vec<Box<dyn Window>>,
+    screen_size: Vec2,
+    pub style: WindowStyle,
+}
+
+impl FloatingWindowManager {
+    pub fn new(screen_size: UVec2, style: WindowStyle) -> Self {
+        Self {
+            windows: Slotmap::with_growth_policy(16, GrowthPolicy::Double),
+            z_order: Vec::new(),
+            focused: None,
+            interaction: Interaction::None,
+            input: WindowInput::default(),
+            closed_windows: Vec::new(),
+            screen_size: screen_size.as_vec2(),
+            style,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.windows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.windows.is_empty()
+    }
+
+    /// The window content gets its requested size, the position is the center of the window.\
+    /// The new window is placed in front of the others and focused
+    pub fn add_window(&mut self, window: Box<dyn Window>, position: Vec2) -> WindowKey {
+        let size = self.style.window_size(window.get_requested_size());
+        self.add_window_with_rect(window, Rect { position, size })
+    }
+
+    /// Same as `add_window`, but the rect of the whole window is given, e.g. to keep the rect a docked window had
+    pub fn add_window_with_rect(&mut self, window: Box<dyn Window>, rect: Rect) -> WindowKey {
+        let rect = self.clamp_to_screen(rect);
+        let key = self
+            .windows
+            .push(FloatingWindow { window, rect })
+            .expect("The window slotmap could not grow");
+        self.z_order.push(key);
+        self.focused = Some(key);
+        key
+    }
+
+    /// Removes the window from the manager without adding it to the closed windows
+    pub fn remove_window(&mut self, key: WindowKey) -> Option<Box<dyn Window>> {
+        let floating_window = self.windows.remove(key)?;
+        self.z_order.retain(|other| *other != key);
+        if self.focused == Some(key) {
+            self.focused = None;
+        }
+        if self.interaction_key() == Some(key) {
+            self.interaction = Interaction::None;
+        }
+        Some(floating_window.window)
+    }
+
+    /// Removes the window and keeps it until `drain_closed_windows` is called
+    pub fn close_window(&mut self, key: WindowKey) -> bool {
+        match self.remove_window(key) {
+            Some(window) => {
+                self.closed_windows.push(window);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The windows closed by the user or by `close_window`
+    pub fn drain_closed_windows(&mut self) -> std::vec::Drain<'_, Box<dyn Window>> {
+        self.closed_windows.drain(..)
+    }
+
+    pub fn window(&self, key: WindowKey) -> Option<&dyn Window> {
+        self.windows
+            .get_value(&key)
+            .map(|floating_window| floating_window.window.as_ref())
+    }
+
+    pub fn window_mut(&mut self, key: WindowKey) -> Option<&mut (dyn Window + 'static)> {
+        self.windows
+            .get_value_mut(&key)
+            .map(|floating_window| floating_window.window.as_mut())
+    }
+
+    pub fn window_rect(&self, key: WindowKey) -> Option<Rect> {
+        self.windows
+            .get_value(&key)
+            .map(|floating_window| floating_window.rect)
+    }
+
+    pub fn set_window_rect(&mut self, key: WindowKey, rect: Rect) -> bool {
+        let rect = self.clamp_to_screen(rect);
+        match self.windows.get_value_mut(&key) {
+            Some(floating_window) => {
+                floating_window.rect = rect;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn content_rect(&self, key: WindowKey) -> Option<Rect> {
+        self.window_rect(key)
+            .map(|rect| self.style.content_rect(rect))
+    }
+
+    /// The keys of the windows from back to front
+    pub fn z_order(&self) -> &[WindowKey] {
+        &self.z_order
+    }
+
+    pub fn focused(&self) -> Option<WindowKey> {
+        self.focused
+    }
+
+    /// Brings the window to the front and focuses it
+    pub fn focus(&mut self, key: WindowKey) -> bool {
+        let Some(index) = self.z_order.iter().position(|other| *other == key) else {
+            return false;
+        };
+        self.z_order.remove(index);
+        self.z_order.push(key);
+        self.focused = Some(key);
+        true
+    }
+
+    /// The front most window that contains the position
+    pub fn window_at(&self, position: Vec2) -> Option<WindowKey> {
+        self.z_order.iter().rev().copied().find(|key| {
+            self.windows
+                .get_value(key)
+                .is_some_and(|floating_window| floating_window.rect.inside_rect(position))
+        })
+    }
+
+    /// The window that is being moved by the user
+    pub fn moving_window(&self) -> Option<WindowKey> {
+        match self.interaction {
+            Interaction::Moving { key, .. } => Some(key),
+            _ => None,
+        }
+    }
+
+    /// Starts moving the window as if its title bar was grabbed, e.g. after it is undocked from a tab.\
+    /// The offset goes from the cursor to the center of the window
+    pub fn start_moving(&mut self, key: WindowKey, grab_offset: Vec2) -> bool {
+        if !self.focus(key) {
+            return false;
+        }
+        self.interaction = Interaction::Moving { key, grab_offset };
+        true
+    }
+
+    pub fn input(&self) -> &WindowInput {
+        &self.input
+    }
+
+    fn interaction_key(&self) -> Option<WindowKey> {
+        match self.interaction {
+            Interaction::None => None,
+            Interaction::Moving { key, .. } | Interaction::Resizing { key, .. } => Some(key),
+        }
+    }
+
+    /// Keeps the title bar inside the screen, so the window can always be moved back
+    fn clamp_to_screen(&self, mut rect: Rect) -> Rect {
+        let half_height = rect.size.y * 0.5;
+        let top = (rect.position.y + half_height)
+            .min(self.screen_size.y)
+            .max(self.style.title_bar_height);
+        rect.position.y = top - half_height;
+        rect.position.x = rect.position.x.clamp(0.0, self.screen_size.x.max(0.0));
+        rect
+    }
+
+    /// Moves, resizes, focuses and closes the windows.\
+    /// The mouse button events used by the windows are consumed, so they are not handled by what is behind them
+    pub fn handle_event(&mut self, event: &mut UIEvent) {
+        self.input.handle_event(event);
+        let consumed = match event {
+            UIEvent::Resize(size) => {
+                self.screen_size = size.as_vec2();
+                let keys: Vec<WindowKey> = self.windows.keys().collect();
+                for key in keys {
+                    if let Some(rect) = self.window_rect(key) {
+                        self.set_window_rect(key, rect);
+                    }
+                }
+                false
+            }
+            UIEvent::MouseMove { .. } => self.drag(),
+            UIEvent::MouseButton(mouse_input) if mouse_input.is_left_pressed() => self.press(),
+            UIEvent::MouseButton(mouse_input) if mouse_input.is_left_released() => {
+                let was_interacting = self.interaction != Interaction::None;
+                self.interaction = Interaction::None;
+                was_interacting
+            }
+            _ => false,
+        };
+        if consumed {
+            event.consume();
+        }
+    }
+
+    fn press(&mut self) -> bool {
+        let cursor_position = self.input.cursor_position;
+        let Some(key) = self.window_at(cursor_position) else {
+            self.focused = None;
+            return false;
+        };
+        self.focus(key);
+
+        let floating_window = self
+            .windows
+            .get_value(&key)
+            .expect("Window in the z order is not in the slotmap");
+        let rect = floating_window.rect;
+        if self.style.close_button_rect(rect).inside_rect(cursor_position) {
+            self.close_window(key);
+        } else if floating_window.window.allow_resize()
+            && self.style.resize_handle_rect(rect).inside_rect(cursor_position)
+        {
+            let bottom_right = rect.position + vec2(rect.size.x, -rect.size.y) * 0.5;
+            self.interaction = Interaction::Resizing {
+                key,
+                grab_offset: bottom_right - cursor_position,
+            };
+        } else if self.style.title_bar_rect(rect).inside_rect(cursor_position) {
+            self.interaction = Interaction::Moving {
+                key,
+                grab_offset: rect.position - cursor_position,
+            };
+        }
+        true
+    }
+
+    fn drag(&mut self) -> bool {
+        let cursor_position = self.input.cursor_position;
+        let (key, rect) = match self.interaction {
+            Interaction::None => return false,
+            Interaction::Moving { key, grab_offset } => {
+                let Some(rect) = self.window_rect(key) else {
+                    return false;
+                };
+                (
+                    key,
+                    Rect {
+                        position: cursor_position + grab_offset,
+                        size: rect.size,
+                    },
+                )
+            }
+            Interaction::Resizing { key, grab_offset } => {
+                let Some(rect) = self.window_rect(key) else {
+                    return false;
+                };
+                let top_left = rect.top_left_position();
+                let bottom_right = cursor_position + grab_offset;
+                let size = vec2(bottom_right.x - top_left.x, top_left.y - bottom_right.y)
+                    .max(self.style.min_size);
+                (
+                    key,
+                    Rect {
+                        position: top_left + vec2(size.x, -size.y) * 0.5,
+                        size,
+                    },
+                )
+            }
+        };
+        self.set_window_rect(key, rect);
+        true
+    }
+
+    /// Draws the windows from back to front and updates their content
+    pub fn update(
+        &mut self,
+        gui_rects: &mut GUIRects,
+        font: Option<WindowFont>,
+        public_data: &mut PublicDataSlotmap,
+    ) {
+        let hovered = self.window_at(self.input.cursor_position);
+        for key in self.z_order.clone() {
+            let Some(floating_window) = self.windows.get_value_mut(&key) else {
+                continue;
+            };
+            //Windows that cannot be resized always get the size they request
+            if !floating_window.window.allow_resize() {
+                let top_left = floating_window.rect.top_left_position();
+                let size = self
+                    .style
+                    .window_size(floating_window.window.get_requested_size());
+                floating_window.rect = Rect {
+                    position: top_left + vec2(size.x, -size.y) * 0.5,
+                    size,
+                };
+            }
+
+            let focused = self.focused == Some(key);
+            draw_window_frame(gui_rects, &self.style, floating_window, focused, font.as_ref());
+            let mut context = WindowContext {
+                rect: self.style.content_rect(floating_window.rect),
+                input: &self.input,
+                hovered: hovered == Some(key),
+                focused,
+                gui_rects,
+                font,
+                public_data,
+            };
+            floating_window.window.update(&mut context);
+        }
+        self.input.clear_frame_input();
+    }
+}
+
+fn draw_window_frame(
+    gui_rects: &mut GUIRects,
+    style: &WindowStyle,
+    floating_window: &FloatingWindow,
+    focused: bool,
+    font: Option<&WindowFont>,
+) {
+    let rect = floating_window.rect;
+    let border = Border {
+        size: style.border_size,
+        color: ExtraBufferData::NewData(style.border_color),
+    };
+    ElementBuilder::new_with_rect(rect)
+        .set_round_rect(BorderRadius::ForAll(style.border_radius).into())
+        .set_color(style.background_color.into())
+        .set_border(Some(border))
+        .build(gui_rects);
+
+    let title_bar = style.title_bar_rect(rect);
+    let title_bar_color = if focused {
+        style.focused_title_bar_color
+    } else {
+        style.title_bar_color
+    };
+    ElementBuilder::new_with_rect(title_bar)
+        .set_round_rect(
+            BorderRadius::ForTopBottom {
+                top: style.border_radius,
+                bottom: 0.0,
+            }
+            .into(),
+        )
+        .set_color(title_bar_color.into())
+        .build(gui_rects);
+
+    let close_button = style.close_button_rect(rect);
+    if let Some(font) = font {
+        let title_mask = title_bar.offset_size(vec2(-style.title_bar_height, 0.0));
+        let title_mask = title_mask.offset_position(vec2(-style.title_bar_height * 0.5, 0.0));
+        draw_text(
+            gui_rects,
+            font,
+            floating_window.window.get_name(),
+            title_bar.left_position() + vec2(style.title_padding, 0.0),
+            style.title_color,
+            title_mask,
+        );
+    }
+    ElementBuilder::new_with_rect(close_button)
+        .set_circle()
+        .set_color(style.close_button_color.into())
+        .build(gui_rects);
+
+    if floating_window.window.allow_resize() {
+        ElementBuilder::new_with_rect(style.resize_handle_rect(rect))
+            .set_round_rect(BorderRadius::ForAll(2.0).into())
+            .set_color(style.resize_handle_color.into())
+            .build(gui_rects);
+    }
+}
@@ -0,0 +1,120 @@
+pub mod docking;
+pub mod manager;
+
+use glam::{vec2, Vec2};
+
+use crate::{
+    color::RGBA,
+    entity_component::public_data::PublicDataSlotmap,
+    font::{font_layout::create_single_line, font_load_gpu::FontCollection},
+    gui::rect_ui::{element::builder::ElementBuilder, event::UIEvent, GUIRects, Rect},
+};
+
+pub use docking::{DockLayout, DockPath, DockPosition, DockStyle, DockTarget, DockingManager};
+pub use manager::{FloatingWindowManager, WindowKey, WindowStyle};
+
+/// Content of a GUI window.\
+/// The update function does not know where the window is placed, the layout system that owns the window sends the
+/// rect it can use, so the same window can be floating, docked or inside a tab
+pub trait Window: Send + Sync {
+    fn get_name(&self) -> &str;
+
+    /// Only `get_name` might be used while the window is hidden, e.g. in a tab that is not selected
+    fn update(&mut self, context: &mut WindowContext);
+
+    fn allow_resize(&self) -> bool;
+
+    /// Size of the content the window would like to have, the layout system can give it a different one
+    fn get_requested_size(&self) -> Vec2;
+}
+
+/// Font used by the layout systems to write the window titles
+#[derive(Clone, Copy)]
+pub struct WindowFont<'a> {
+    pub collection: &'a FontCollection,
+    pub index: usize,
+    pub size: f32,
+}
+
+/// Mouse state of the frame, the cursor position uses the same coordinates as the GUI rects
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WindowInput {
+    pub cursor_position: Vec2,
+    pub left_down: bool,
+    /// The left button was pressed this frame
+    pub left_pressed: bool,
+    /// The left button was released this frame
+    pub left_released: bool,
+    pub scroll: Vec2,
+}
+
+impl WindowInput {
+    pub fn handle_event(&mut self, event: &UIEvent) {
+        match event {
+            UIEvent::MouseMove { corrected, .. } => self.cursor_position = *corrected,
+            UIEvent::MouseButton(mouse_input) => {
+                if mouse_input.is_left_pressed() {
+                    self.left_down = true;
+                    self.left_pressed = true;
+                } else if mouse_input.is_left_released() {
+                    self.left_down = false;
+                    self.left_released = true;
+                }
+            }
+            UIEvent::MouseWheel(delta) => self.scroll += *delta,
+            _ => {}
+        }
+    }
+
+    /// Forgets the button changes and the scroll, should be called after the windows are updated
+    pub fn clear_frame_input(&mut self) {
+        self.left_pressed = false;
+        self.left_released = false;
+        self.scroll = Vec2::ZERO;
+    }
+}
+
+/// Data sent to `Window::update`
+pub struct WindowContext<'a> {
+    /// Area the window content can use
+    pub rect: Rect,
+    pub input: &'a WindowInput,
+    pub hovered: bool,
+    pub focused: bool,
+    pub gui_rects: &'a mut GUIRects,
+    pub font: Option<WindowFont<'a>>,
+    /// The windows cannot update each other directly, they communicate through the Public Data Slotmap
+    pub public_data: &'a mut PublicDataSlotmap,
+}
+
+impl WindowContext<'_> {
+    pub fn cursor_position(&self) -> Vec2 {
+        self.input.cursor_position
+    }
+}
+
+/// Writes a single line of SDF text, the position is the left center of the line.\
+/// The characters outside the mask are not rendered, returns the rect of the text
+pub fn draw_text(
+    gui_rects: &mut GUIRects,
+    font: &WindowFont,
+    text: &str,
+    position: Vec2,
+    color: RGBA,
+    rect_mask: Rect,
+) -> Rect {
+    let (font_elements, text_rect) =
+        create_single_line(text, font.size, font.collection, font.index, 0.0);
+    let offset = position - vec2(0.0, text_rect.size.y * 0.5);
+    for font_element in font_elements {
+        ElementBuilder::new_with_rect(font_element.rect.offset_position(offset))
+            .set_sdffont(font_element.tx_slice.into())
+            .set_color(color.into())
+            .set_rect_mask(rect_mask.into())
+            .build(gui_rects);
+    }
+    text_rect.offset_position(offset)
+}
+
+#[cfg(test)]
+mod test;
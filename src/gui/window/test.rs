@@ -0,0 +1,312 @@
+#[cfg(test)]
+mod tests {
+    use glam::{uvec2, vec2, Vec2};
+    use winit::event::{ElementState, MouseButton};
+
+    use crate::gui::{
+        rect_ui::event::{MouseInput, UIEvent},
+        window::{
+            docking::DockLayoutNode, DockLayout, DockPath, DockPosition, DockStyle, DockTarget, DockingManager,
+            FloatingWindowManager, Window, WindowContext, WindowStyle,
+        },
+    };
+
+    struct TestWindow {
+        name: &'static str,
+        resizable: bool,
+    }
+
+    impl Window for TestWindow {
+        fn get_name(&self) -> &str {
+            self.name
+        }
+
+        fn update(&mut self, _context: &mut WindowContext) {}
+
+        fn allow_resize(&self) -> bool {
+            self.resizable
+        }
+
+        fn get_requested_size(&self) -> Vec2 {
+            vec2(200.0, 100.0)
+        }
+    }
+
+    fn test_window(name: &'static str, resizable: bool) -> Box<dyn Window> {
+        Box::new(TestWindow { name, resizable })
+    }
+
+    /// Implemented by the layout systems so the tests can send them the same mouse events
+    trait HandleEvent {
+        fn handle(&mut self, event: &mut UIEvent);
+    }
+
+    impl HandleEvent for FloatingWindowManager {
+        fn handle(&mut self, event: &mut UIEvent) {
+            self.handle_event(event);
+        }
+    }
+
+    impl HandleEvent for DockingManager {
+        fn handle(&mut self, event: &mut UIEvent) {
+            self.handle_event(event);
+        }
+    }
+
+    fn move_cursor(manager: &mut impl HandleEvent, position: Vec2) -> bool {
+        let mut event = UIEvent::MouseMove {
+            corrected: position,
+            raw: position,
+        };
+        manager.handle(&mut event);
+        matches!(event, UIEvent::Consumed)
+    }
+
+    fn left_button(manager: &mut impl HandleEvent, state: ElementState) -> bool {
+        let mut event = UIEvent::MouseButton(MouseInput {
+            button: MouseButton::Left,
+            state,
+        });
+        manager.handle(&mut event);
+        matches!(event, UIEvent::Consumed)
+    }
+
+    fn click(manager: &mut impl HandleEvent, position: Vec2) -> bool {
+        move_cursor(manager, position);
+        let consumed = left_button(manager, ElementState::Pressed);
+        left_button(manager, ElementState::Released);
+        consumed
+    }
+
+    #[test]
+    fn clicking_a_window_focuses_it_and_brings_it_to_the_front(){
+        let mut manager = FloatingWindowManager::new(uvec2(800, 600), WindowStyle::default());
+        let back = manager.add_window(test_window("back", true), vec2(300.0, 300.0));
+        let front = manager.add_window(test_window("front", true), vec2(350.0, 300.0));
+        assert_eq!(manager.z_order(), &[back, front]);
+        assert_eq!(manager.focused(), Some(front));
+
+        //Only the back window is under the cursor
+        assert!(click(&mut manager, vec2(210.0, 280.0)));
+        assert_eq!(manager.z_order(), &[front, back]);
+        assert_eq!(manager.focused(), Some(back));
+
+        assert!(!click(&mut manager, vec2(10.0, 10.0)));
+        assert_eq!(manager.focused(), None);
+    }
+
+    #[test]
+    fn windows_are_moved_resized_and_closed_with_the_mouse(){
+        let style = WindowStyle::default();
+        let mut manager = FloatingWindowManager::new(uvec2(800, 600), style);
+        let key = manager.add_window(test_window("window", true), vec2(400.0, 300.0));
+        let rect = manager.window_rect(key).unwrap();
+        assert_eq!(rect.size, vec2(200.0, 100.0 + style.title_bar_height));
+
+        //Drag the title bar
+        let title_bar = style.title_bar_rect(rect);
+        move_cursor(&mut manager, title_bar.position);
+        left_button(&mut manager, ElementState::Pressed);
+        assert_eq!(manager.moving_window(), Some(key));
+        assert!(move_cursor(&mut manager, title_bar.position + vec2(50.0, -20.0)));
+        left_button(&mut manager, ElementState::Released);
+        assert_eq!(manager.window_rect(key).unwrap().position, rect.position + vec2(50.0, -20.0));
+
+        //Drag the resize handle, the size cannot be smaller than the minimum size
+        let rect = manager.window_rect(key).unwrap();
+        let handle = style.resize_handle_rect(rect);
+        move_cursor(&mut manager, handle.position);
+        left_button(&mut manager, ElementState::Pressed);
+        move_cursor(&mut manager, handle.position + vec2(40.0, 1000.0));
+        left_button(&mut manager, ElementState::Released);
+        let resized = manager.window_rect(key).unwrap();
+        assert_eq!(resized.size.x, rect.size.x + 40.0);
+        assert_eq!(resized.size.y, style.min_size.y.max(rect.size.y - 1000.0));
+        assert_eq!(resized.top_left_position(), rect.top_left_position());
+
+        assert!(click(&mut manager, style.close_button_rect(resized).position));
+        assert!(manager.is_empty());
+        let closed: Vec<String> = manager
+            .drain_closed_windows()
+            .map(|window| window.get_name().to_string())
+            .collect();
+        assert_eq!(closed, vec!["window".to_string()]);
+    }
+
+    #[test]
+    fn fixed_size_windows_cannot_be_resized(){
+        let style = WindowStyle::default();
+        let mut manager = FloatingWindowManager::new(uvec2(800, 600), style);
+        let key = manager.add_window(test_window("fixed", false), vec2(400.0, 300.0));
+        let rect = manager.window_rect(key).unwrap();
+        let handle = style.resize_handle_rect(rect);
+        move_cursor(&mut manager, handle.position);
+        left_button(&mut manager, ElementState::Pressed);
+        move_cursor(&mut manager, handle.position + vec2(100.0, -100.0));
+        assert_eq!(manager.window_rect(key).unwrap().size, rect.size);
+    }
+
+    fn docking_manager() -> DockingManager {
+        DockingManager::new(uvec2(800, 600), DockStyle::default(), WindowStyle::default())
+    }
+
+    fn dock(manager: &mut DockingManager, name: &'static str, path: DockPath, position: DockPosition) {
+        let target = DockTarget { path, position };
+        assert!(manager.dock(test_window(name, true), &target).is_ok());
+    }
+
+    #[test]
+    fn docking_splits_tab_groups_and_undocking_collapses_them(){
+        let mut manager = docking_manager();
+        dock(&mut manager, "scene", DockPath::root(), DockPosition::Center);
+        dock(&mut manager, "game", DockPath::root(), DockPosition::Center);
+        dock(&mut manager, "inspector", DockPath::root(), DockPosition::Right);
+
+        let groups = manager.tab_group_rects();
+        assert_eq!(groups.len(), 2);
+        let (left_path, left_rect) = groups[0].clone();
+        let (right_path, right_rect) = groups[1].clone();
+        assert!(left_rect.position.x < right_rect.position.x);
+        assert_eq!(manager.tab_names(&left_path), Some((vec!["scene", "game"], 1)));
+        assert_eq!(manager.tab_names(&right_path), Some((vec!["inspector"], 0)));
+
+        let inspector = manager.undock(&right_path, 0).unwrap();
+        assert_eq!(inspector.get_name(), "inspector");
+        let groups = manager.tab_group_rects();
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].1.size, vec2(800.0, 600.0));
+        assert_eq!(manager.find_window("game"), Some((DockPath::root(), 1)));
+    }
+
+    #[test]
+    fn tabs_are_undocked_by_dragging_and_docked_on_the_targets(){
+        let style = DockStyle::default();
+        let mut manager = docking_manager();
+        dock(&mut manager, "scene", DockPath::root(), DockPosition::Center);
+        dock(&mut manager, "game", DockPath::root(), DockPosition::Center);
+
+        //Drag the second tab out of the tab bar
+        let tab_position = vec2(style.tab_width * 1.5, 600.0 - style.tab_bar_height * 0.5);
+        move_cursor(&mut manager, tab_position);
+        assert!(left_button(&mut manager, ElementState::Pressed));
+        move_cursor(&mut manager, vec2(400.0, 400.0));
+        assert_eq!(manager.tab_names(&DockPath::root()), Some((vec!["scene"], 0)));
+        let floating = manager.floating.moving_window().unwrap();
+        assert_eq!(manager.floating.window(floating).unwrap().get_name(), "game");
+
+        //Drop it on the right target of the remaining group
+        let right_target = vec2(400.0 + style.dock_target_size * 1.25, 300.0);
+        move_cursor(&mut manager, right_target);
+        assert!(left_button(&mut manager, ElementState::Released));
+        assert!(manager.floating.is_empty());
+        let groups = manager.tab_group_rects();
+        assert_eq!(manager.tab_names(&groups[1].0), Some((vec!["game"], 0)));
+    }
+
+    #[test]
+    fn splitters_change_the_ratio_of_the_split(){
+        let mut manager = docking_manager();
+        dock(&mut manager, "top", DockPath::root(), DockPosition::Center);
+        dock(&mut manager, "bottom", DockPath::root(), DockPosition::Bottom);
+
+        move_cursor(&mut manager, vec2(400.0, 300.0));
+        assert!(left_button(&mut manager, ElementState::Pressed));
+        move_cursor(&mut manager, vec2(400.0, 450.0));
+        left_button(&mut manager, ElementState::Released);
+
+        let groups = manager.tab_group_rects();
+        let top_rect = groups[0].1;
+        assert_eq!(top_rect.position.y + top_rect.size.y * 0.5, 600.0);
+        assert_eq!(top_rect.size.y, 150.0 - DockStyle::default().splitter_size * 0.5);
+    }
+
+    #[test]
+    fn focused_paths_follow_the_tab_groups_when_the_tree_changes(){
+        let mut manager = docking_manager();
+        dock(&mut manager, "scene", DockPath::root(), DockPosition::Center);
+        click(&mut manager, vec2(400.0, 300.0));
+        assert_eq!(manager.focused_group(), Some(&DockPath::root()));
+
+        //The focused group moves into the new split
+        dock(&mut manager, "inspector", DockPath::root(), DockPosition::Right);
+        let (scene_path, _) = manager.find_window("scene").unwrap();
+        assert_ne!(scene_path, DockPath::root());
+        assert_eq!(manager.focused_group(), Some(&scene_path));
+
+        //And back to the root when the split collapses
+        let (inspector_path, _) = manager.find_window("inspector").unwrap();
+        manager.undock(&inspector_path, 0).unwrap();
+        assert_eq!(manager.focused_group(), Some(&DockPath::root()));
+
+        //Removing the focused group clears the focus
+        dock(&mut manager, "inspector", DockPath::root(), DockPosition::Right);
+        click(&mut manager, vec2(600.0, 300.0));
+        let (inspector_path, _) = manager.find_window("inspector").unwrap();
+        assert_eq!(manager.focused_group(), Some(&inspector_path));
+        manager.undock(&inspector_path, 0).unwrap();
+        assert_eq!(manager.focused_group(), None);
+    }
+
+    #[test]
+    fn pressed_tabs_are_undocked_after_their_group_is_split(){
+        let style = DockStyle::default();
+        let mut manager = docking_manager();
+        dock(&mut manager, "scene", DockPath::root(), DockPosition::Center);
+        dock(&mut manager, "game", DockPath::root(), DockPosition::Center);
+
+        let tab_position = vec2(style.tab_width * 1.5, 600.0 - style.tab_bar_height * 0.5);
+        move_cursor(&mut manager, tab_position);
+        assert!(left_button(&mut manager, ElementState::Pressed));
+        dock(&mut manager, "inspector", DockPath::root(), DockPosition::Right);
+        move_cursor(&mut manager, vec2(200.0, 300.0));
+
+        let floating = manager.floating.moving_window().unwrap();
+        assert_eq!(manager.floating.window(floating).unwrap().get_name(), "game");
+        let (scene_path, _) = manager.find_window("scene").unwrap();
+        assert_eq!(manager.tab_names(&scene_path), Some((vec!["scene"], 0)));
+    }
+
+    #[test]
+    fn layouts_are_restored_from_json(){
+        let mut manager = docking_manager();
+        dock(&mut manager, "scene", DockPath::root(), DockPosition::Center);
+        dock(&mut manager, "console", DockPath::root(), DockPosition::Bottom);
+        let (console_path, _) = manager.find_window("console").unwrap();
+        dock(&mut manager, "log", console_path, DockPosition::Center);
+        manager.floating.add_window(test_window("palette", true), vec2(200.0, 200.0));
+        let layout = manager.layout();
+        let json = layout.to_json().unwrap();
+
+        let mut restored = docking_manager();
+        let windows = ["palette", "log", "scene", "console", "unused"]
+            .into_iter()
+            .map(|name| test_window(name, true))
+            .collect();
+        let unused = restored.restore_layout(&DockLayout::from_json(&json).unwrap(), windows);
+
+        assert_eq!(restored.layout(), layout);
+        let unused: Vec<&str> = unused.iter().map(|window| window.get_name()).collect();
+        assert_eq!(unused, vec!["unused"]);
+    }
+
+    #[test]
+    fn restored_split_ratios_are_clamped(){
+        let mut manager = docking_manager();
+        dock(&mut manager, "left", DockPath::root(), DockPosition::Center);
+        dock(&mut manager, "right", DockPath::root(), DockPosition::Right);
+        let mut layout = manager.layout();
+        let Some(DockLayoutNode::Split { ratio, .. }) = layout.root.as_mut() else {
+            panic!("The root should be a split");
+        };
+        *ratio = 5.0;
+
+        let mut restored = docking_manager();
+        let windows = vec![test_window("left", true), test_window("right", true)];
+        assert!(restored.restore_layout(&layout, windows).is_empty());
+        let max_ratio = 1.0 - DockStyle::default().min_split_ratio;
+        assert!(matches!(
+            restored.layout().root,
+            Some(DockLayoutNode::Split { ratio, .. }) if ratio == max_ratio
+        ));
+    }
+}
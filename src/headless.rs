@@ -0,0 +1,94 @@
+use std::collections::VecDeque;
+
+use crate::{
+    engine::time::Microsecond,
+    graphics::frame_capture::{CaptureError, ImageSequence},
+    run_frame, Engine, EngineEvent, Runtime,
+};
+
+/// Runs the frames of an engine without a window or an event loop, usually one created with `Engine::new_headless`.\
+/// Every frame lasts `frame_delta` of simulated time, so the results do not depend on the speed of the machine
+pub struct HeadlessLoop {
+    pub frame_delta: Microsecond,
+    event_queue: VecDeque<EngineEvent>,
+    exit_requested: bool,
+}
+
+impl HeadlessLoop {
+    pub fn new(engine: &mut Engine, frame_delta: Microsecond) -> Self {
+        engine.timer.reset();
+        Self {
+            frame_delta,
+            event_queue: VecDeque::with_capacity(100),
+            exit_requested: false,
+        }
+    }
+
+    /// The event is handled by the runtime in the next frame
+    pub fn push_event(&mut self, event: EngineEvent) {
+        self.event_queue.push_back(event);
+    }
+
+    /// The runtime called the exit function, no more frames are run
+    pub fn exit_requested(&self) -> bool {
+        self.exit_requested
+    }
+
+    /// Runs a single frame, returns false if the runtime has requested to exit
+    pub fn step<R: 'static + Runtime>(
+        &mut self,
+        engine: &mut Engine,
+        runtime: &mut R,
+    ) -> Result<bool, wgpu::SurfaceError> {
+        if self.exit_requested {
+            return Ok(false);
+        }
+        engine.timer.advance_frame(self.frame_delta);
+        let exit_requested = &mut self.exit_requested;
+        let mut close_app = || *exit_requested = true;
+        run_frame(engine, runtime, &mut self.event_queue, &mut close_app)?;
+        Ok(!self.exit_requested)
+    }
+
+    /// Runs up to `frame_count` frames, it stops early if the runtime requests to exit.\
+    /// Returns the number of frames that were run
+    pub fn run_frames<R: 'static + Runtime>(
+        &mut self,
+        engine: &mut Engine,
+        runtime: &mut R,
+        frame_count: u32,
+    ) -> Result<u32, wgpu::SurfaceError> {
+        for frame in 0..frame_count {
+            if self.exit_requested {
+                return Ok(frame);
+            }
+            self.step(engine, runtime)?;
+        }
+        Ok(frame_count)
+    }
+
+    /// Same as `run_frames`, but the render target is saved to the image sequence after every frame.\
+    /// Since the frame delta is fixed, the sequence is the same every time and can be turned into a video
+    pub fn record_frames<R: 'static + Runtime>(
+        &mut self,
+        engine: &mut Engine,
+        runtime: &mut R,
+        frame_count: u32,
+        image_sequence: &mut ImageSequence,
+    ) -> Result<u32, CaptureError> {
+        for frame in 0..frame_count {
+            if self.exit_requested {
+                return Ok(frame);
+            }
+            self.step(engine, runtime)?;
+            let captured_frame = engine.graphics.capture_render_target()?;
+            image_sequence.save_frame(&captured_frame)?;
+        }
+        Ok(frame_count)
+    }
+
+    /// Same as the end of the event loop, gives the runtime a chance to clean up
+    pub fn exit<R: 'static + Runtime>(self, engine: &Engine, runtime: &mut R) {
+        runtime.before_exit(engine);
+    }
+}
@@ -6,6 +6,7 @@ pub mod color;
 pub mod font;
 pub mod graphics;
 pub mod gui;
+pub mod headless;
 pub mod math_utils;
 pub use bytemuck;
 use glam::{uvec2, UVec2};
@@ -14,6 +15,8 @@ pub use wgpu;
 pub use winit;
 pub mod engine;
 pub use engine::Engine;
+use graphics::render_target::RenderTarget;
+pub use headless::HeadlessLoop;
 pub use slotmap;
 pub mod entity_component;
 pub mod runtime;
@@ -57,19 +60,27 @@ pub fn render<R: 'static + Runtime>(
     engine: &mut Engine,
     runtime: &mut R,
 ) -> Result<Microsecond, wgpu::SurfaceError> {
-    let output: wgpu::SurfaceTexture = engine
-        .graphics
-        .render_window
-        .surface
-        .get_current_texture()?;
-
-    if output.suboptimal {
-        println!("Suboptimal surface!!");
-    }
+    let output = match &engine.graphics.render_target {
+        RenderTarget::Surface(render_window) => {
+            let output: wgpu::SurfaceTexture = render_window.surface.get_current_texture()?;
+            if output.suboptimal {
+                println!("Suboptimal surface!!");
+            }
+            Some(output)
+        }
+        RenderTarget::Texture(_) => None,
+    };
 
-    let screen_view = output
-        .texture
-        .create_view(&wgpu::TextureViewDescriptor::default());
+    let surface_view = output.as_ref().map(|output| {
+        output
+            .texture
+            .create_view(&wgpu::TextureViewDescriptor::default())
+    });
+    let screen_view = match (&surface_view, &engine.graphics.render_target) {
+        (Some(surface_view), _) => surface_view,
+        (None, RenderTarget::Texture(render_texture)) => &render_texture.texture_view,
+        (None, RenderTarget::Surface(_)) => unreachable!("The surface texture was not acquired"),
+    };
     let mut encoder =
         engine
             .graphics
@@ -78,13 +89,15 @@ pub fn render<R: 'static + Runtime>(
                 label: Some("Render Encoder"),
             });
 
-    runtime.render(engine, &screen_view, &mut encoder);
+    runtime.render(engine, screen_view, &mut encoder);
 
     let gpu_lock_time_start = std::time::Instant::now();
     let mut command_buffers = Vec::<wgpu::CommandBuffer>::new();
     command_buffers.push(encoder.finish());
     engine.graphics.queue.submit(command_buffers);
-    output.present();
+    if let Some(output) = output {
+        output.present();
+    }
     engine.graphics.device.poll(wgpu::Maintain::Wait);
     //pollster::block_on(on_gpu_done);
 
@@ -170,6 +183,55 @@ fn push_event(event_queue: &mut VecDeque<EngineEvent>, event: WindowOrDeviceEven
     }
 }
 
+/// Runs the update and render of a single frame, the event queue is cleared after the events are handled.\
+/// `frame_end` only runs if the frame was rendered
+pub(crate) fn run_frame<R: 'static + Runtime, F: FnMut()>(
+    engine: &mut Engine,
+    runtime: &mut R,
+    event_queue: &mut VecDeque<EngineEvent>,
+    close_app: &mut F,
+) -> Result<(), wgpu::SurfaceError> {
+    let frame_start_time = std::time::Instant::now();
+    runtime.frame_start(engine);
+    engine.operation_timer.frame_start_time =
+        Microsecond(frame_start_time.elapsed().as_micros());
+
+    engine.timer.update_buffer(&engine.graphics.queue);
+
+    let event_handling_time = std::time::Instant::now();
+    runtime.handle_event_queue(event_queue, engine, close_app);
+    engine.operation_timer.event_handling_time =
+        Microsecond(event_handling_time.elapsed().as_micros());
+
+    event_queue.clear();
+
+    let fixed_update_time = std::time::Instant::now();
+    engine
+        .timer
+        .fixed_timestep
+        .run_steps(|| runtime.fixed_update(engine, close_app));
+    engine.operation_timer.fixed_update_time =
+        Microsecond(fixed_update_time.elapsed().as_micros());
+
+    let update_time = std::time::Instant::now();
+    runtime.update(engine, close_app);
+    engine.operation_timer.update_time =
+        Microsecond(update_time.elapsed().as_micros());
+
+    let render_time = std::time::Instant::now();
+    let render_result = render(engine, runtime);
+    engine.operation_timer.render_time =
+        Microsecond(render_time.elapsed().as_micros());
+
+    //println!("GPU LOCK TIME {}", gpu_lock_time * 1000.0);
+    engine.operation_timer.gpu_lock_time = render_result?;
+    let operation_time = std::time::Instant::now();
+    runtime.frame_end(engine, close_app);
+    engine.operation_timer.frame_end_time =
+        Microsecond(operation_time.elapsed().as_micros());
+    Ok(())
+}
+
 pub fn start_engine_loop<R: 'static + Runtime>(
     mut engine: Engine,
     mut runtime: R,
@@ -198,39 +260,11 @@ pub fn start_engine_loop<R: 'static + Runtime>(
                         *control_flow = ControlFlow::Exit;
                     };
 
-                    let frame_start_time = std::time::Instant::now();
-                    runtime.frame_start(&engine);
-                    engine.operation_timer.frame_start_time =
-                        Microsecond(frame_start_time.elapsed().as_micros());
-
-                    engine.timer.update_buffer(&engine.graphics.queue);
-
-                    let event_handling_time = std::time::Instant::now();
-                    runtime.handle_event_queue(&event_queue, &mut engine, &mut close_app);
-                    engine.operation_timer.event_handling_time =
-                        Microsecond(event_handling_time.elapsed().as_micros());
-
-                    event_queue.clear();
-
-                    let update_time = std::time::Instant::now();
-                    runtime.update(&engine, &mut close_app);
-                    engine.operation_timer.update_time =
-                        Microsecond(update_time.elapsed().as_micros());
-
-                    let render_time = std::time::Instant::now();
-                    let render_result = render(&mut engine, &mut runtime);
-                    engine.operation_timer.render_time =
-                        Microsecond(render_time.elapsed().as_micros());
+                    let render_result =
+                        run_frame(&mut engine, &mut runtime, &mut event_queue, &mut close_app);
 
                     match render_result {
-                        Ok(gpu_lock_time) => {
-                            //println!("GPU LOCK TIME {}", gpu_lock_time * 1000.0);
-                            engine.operation_timer.gpu_lock_time = gpu_lock_time;
-                            let operation_time = std::time::Instant::now();
-                            runtime.frame_end(&mut engine, &mut close_app);
-                            engine.operation_timer.frame_end_time =
-                                Microsecond(operation_time.elapsed().as_micros());
-                        }
+                        Ok(()) => {}
                         // Reconfigure the surface if lost
                         Err(wgpu::SurfaceError::Lost) => engine.graphics.configure_surface(),
                         // The system is out of memory, we should probably quit
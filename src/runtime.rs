@@ -10,6 +10,9 @@ pub trait Runtime {
     fn handle_event_queue<F>(&mut self, event_queue: &VecDeque<EngineEvent>, engine: &mut Engine, exit_event_loop: &mut F)
     where
         F: FnMut() -> ();
+    /// Runs `engine.timer.fixed_timestep.steps()` times per frame, between the event handling and the update.\
+    /// Each call advances the simulation by `engine.timer.fixed_timestep.step`
+    fn fixed_update(&mut self, _engine: &Engine, _exit_event_loop: &mut dyn FnMut()) {}
     fn update(&mut self, engine: &Engine, exit_event_loop: &mut dyn FnMut() -> ());
     fn render(
         &mut self,